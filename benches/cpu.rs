@@ -0,0 +1,88 @@
+// cargo bench --bench cpu
+// times the cpu core on a small loop over a flat bus, and on nestest in
+// automation mode ($C000) when the rom is found (NESTEST_ROM or ./nestest.nes).
+use std::time::{Duration, Instant};
+
+use rust_nes::bus::Bus;
+use rust_nes::cartridge::rom::ROM;
+use rust_nes::cpu_internals::cpu::CPU;
+use rust_nes::cpu_internals::cpu_bus::{CpuBus, FlatBus};
+
+const ROUNDS: usize = 10;
+const LOOP_INSTRUCTIONS: usize = 5_000_000;
+// lines of nestest.log
+const NESTEST_INSTRUCTIONS: usize = 8991;
+const NESTEST_RUNS: usize = 200;
+
+// run `count` instructions and return the time spent
+fn run<M: CpuBus>(cpu: &mut CPU<M>, count: usize) -> Duration {
+    let mut executed = 0;
+    let start = Instant::now();
+    cpu.run_with_callback(|cpu| {
+        executed += 1;
+        cpu.halted = executed > count;
+    })
+    .unwrap();
+    start.elapsed()
+}
+
+fn report(name: &str, instructions: usize, elapsed: Duration) {
+    let ns = elapsed.as_nanos() as f64 / instructions as f64;
+    println!(
+        "{:<10} {:>10} instructions {:>8.2} ns/instruction {:>8.2} MIPS",
+        name,
+        instructions,
+        ns,
+        1_000.0 / ns
+    );
+}
+
+fn bench_loop() {
+    let mut cpu = CPU::new(FlatBus::new());
+    cpu.load(vec![
+        0xa2, 0x00, // LDX #$00
+        0xbd, 0x00, 0x02, // LDA $0200,X
+        0x69, 0x01, // ADC #$01
+        0x9d, 0x00, 0x02, // STA $0200,X
+        0xe8, // INX
+        0xd0, 0xf5, // BNE $8002
+        0x4c, 0x00, 0x80, // JMP $8000
+    ]);
+    cpu.reset();
+    let best = (0..ROUNDS)
+        .map(|_| run(&mut cpu, LOOP_INSTRUCTIONS))
+        .min()
+        .unwrap();
+    report("loop", LOOP_INSTRUCTIONS, best);
+}
+
+fn bench_nestest() {
+    let path = std::env::var("NESTEST_ROM").unwrap_or(String::from("nestest.nes"));
+    let raw = match std::fs::read(&path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            println!("nestest    skipped, can't read {}: {}", path, e);
+            return;
+        }
+    };
+    let best = (0..ROUNDS)
+        .map(|_| {
+            (0..NESTEST_RUNS)
+                .map(|_| {
+                    let bus = Bus::new(ROM::new(&raw).unwrap(), |_, _, _| {});
+                    let mut cpu = CPU::new(bus);
+                    cpu.reset();
+                    cpu.program_counter = 0xc000;
+                    run(&mut cpu, NESTEST_INSTRUCTIONS)
+                })
+                .sum::<Duration>()
+        })
+        .min()
+        .unwrap();
+    report("nestest", NESTEST_INSTRUCTIONS * NESTEST_RUNS, best);
+}
+
+fn main() {
+    bench_loop();
+    bench_nestest();
+}
@@ -1,9 +1,17 @@
 use bitflags::Flags;
 
 use crate::cartridge;
+use crate::cartridge::cdl::{self, CodeDataLog};
 use crate::cartridge::{mem::*, rom::Mirroring, rom::ROM};
+use crate::cheats::Cheats;
+use crate::cpu_internals::cpu_bus::CpuBus;
+use crate::cpu_internals::opscodes::{AddressingMode, OpCode, OPCODES};
+use crate::debugger::memory::{MemorySpaces, Space};
+use crate::input::{self, InputDevice, Ports};
 use crate::joypad::Joypad;
+use crate::movie::MovieSession;
 use crate::ppu::{NesPPU, PPUMirroring, PPU};
+use crate::region::Region;
 use crate::rendering::frame::Frame;
 
 //  _______________ $10000  _______________
@@ -38,17 +46,33 @@ const RAM: u16 = 0x0000;
 const RAM_END: u16 = 0x1FFF;
 const PPU_REG: u16 = 0x2000;
 const PPU_REG_END: u16 = 0x3FFF;
+// controllers only drive the low bits, the rest is open bus: the $40 of the
+// address the cpu just put there
+const CONTROLLER_OPEN_BUS: u8 = 0x40;
 
 pub struct Bus<'call> {
     // 2kib
     pub cpu_vram: [u8; 2048],
+    // 8kib at $6000, the SRAM on the map above
+    pub prg_ram: [u8; 0x2000],
     pub rom: ROM,
     pub ppu: NesPPU,
-    pub joypad: Joypad,
+    // controller ports 1 and 2, $4016 and $4017, a pad in each by default
+    pub ports: Ports,
     pub frame: Frame,
+    // Game Genie codes and RAM freezes
+    pub cheats: Cheats,
+    // movie recorded or played back, a frame at each vblank start
+    pub movie: Option<MovieSession>,
 
     pub cycles: usize,
-    gameloop_callback: Box<dyn FnMut(&NesPPU, &mut Joypad, &Frame) + 'call>,
+    // leftover PPU dots (in 1/denominator units) for non-integer clock ratios
+    ppu_clock_remainder: usize,
+    // code/data log flags of the PRG ROM, `None` while not logging
+    prg_log: Option<Vec<u8>>,
+    // address and opcode of the instruction being executed, for the log
+    instruction: Option<(u16, &'static OpCode)>,
+    gameloop_callback: Box<dyn FnMut(&NesPPU, &mut Ports, &Frame) + 'call>,
 }
 
 impl ROM {
@@ -64,43 +88,228 @@ impl ROM {
 impl<'a> Bus<'a> {
     pub fn new<'call, F>(rom: ROM, gameloop_callback: F) -> Bus<'call>
     where
-        F: FnMut(&NesPPU, &mut Joypad, &Frame) + 'call,
+        F: FnMut(&NesPPU, &mut Ports, &Frame) + 'call,
     {
-        let ppu = NesPPU::new(rom.chr_rom.clone(), rom.to_PPUMirroring());
+        let mut ppu = NesPPU::new(rom.chr_rom.clone(), rom.to_PPUMirroring());
+        ppu.region = rom.region;
         Bus {
             cpu_vram: [0; 2048],
+            prg_ram: [0; 0x2000],
             rom,
             ppu,
-            joypad: Joypad::new(),
+            ports: [Box::new(Joypad::new()), Box::new(Joypad::new())],
             frame: Frame::new(),
+            cheats: Cheats::new(),
+            movie: None,
             cycles: 0,
+            ppu_clock_remainder: 0,
+            prg_log: None,
+            instruction: None,
             gameloop_callback: Box::from(gameloop_callback),
         }
     }
-    pub fn read_prg_rom(&self, mut addr: u16) -> u8 {
+    pub fn read_prg_rom(&self, addr: u16) -> u8 {
+        self.rom.prg_rom[self.prg_rom_index(addr)]
+    }
+    // what the cpu sees of the cartridge, through the Game Genie
+    fn read_cartridge(&self, addr: u16) -> u8 {
+        self.cheats.read_rom(addr, self.read_prg_rom(addr))
+    }
+    // NROM: 16kib roms are mirrored at $C000
+    fn prg_rom_index(&self, mut addr: u16) -> usize {
         addr -= 0x8000;
         if self.rom.prg_rom.len() == 0x4000 && addr >= 0x4000 {
             addr = addr % 0x4000;
         }
-        self.rom.prg_rom[addr as usize]
+        addr as usize
+    }
+
+    /// log code and data from now on, on top of the flags already in `log`.
+    pub fn start_code_data_log(&mut self, log: CodeDataLog) {
+        self.prg_log = Some(log.prg);
+        *self.ppu.chr_log.get_mut() = Some(log.chr);
+    }
+
+    pub fn code_data_log(&self) -> Option<CodeDataLog> {
+        Some(CodeDataLog {
+            prg: self.prg_log.clone()?,
+            chr: self.ppu.chr_log.borrow().clone().unwrap_or_default(),
+        })
+    }
+
+    fn log_prg(&mut self, addr: u16, flags: u8) {
+        let index = self.prg_rom_index(addr);
+        if let Some(log) = self.prg_log.as_mut() {
+            cdl::mark_prg(log, index, addr, flags);
+        }
+    }
+
+    // a PRG read by an instruction is data, unless it is one of its own bytes
+    // (the dummy read after a single byte opcode included)
+    fn log_prg_read(&mut self, addr: u16) {
+        let flags = match self.instruction {
+            Some((start, opcode)) if addr.wrapping_sub(start) < opcode.bytes_len.max(2) as u16 => {
+                return
+            }
+            Some((_, opcode))
+                if matches!(
+                    opcode.mode,
+                    AddressingMode::Indirect_X | AddressingMode::Indirect_Y
+                ) =>
+            {
+                cdl::DATA | cdl::INDIRECT_DATA
+            }
+            _ => cdl::DATA,
+        };
+        self.log_prg(addr, flags);
+    }
+
+    /// the device in `port` (0 or 1), if it is a `D`.
+    pub fn device_mut<D: InputDevice>(&mut self, port: usize) -> Option<&mut D> {
+        input::device_mut(self.ports.get_mut(port)?.as_mut())
+    }
+
+    pub fn region(&self) -> Region {
+        self.ppu.region
+    }
+    // override the region read from the rom header
+    pub fn set_region(&mut self, region: Region) {
+        self.ppu.region = region;
+        self.ppu_clock_remainder = 0;
     }
 }
-impl<'a> Bus<'a> {
-    pub fn tick(&mut self, cycles: u8) {
+impl CpuBus for Bus<'_> {
+    fn tick(&mut self, cycles: u8) {
         self.cycles += cycles as usize;
-        // ppu cycles 3x faster than cpu
+        // ppu cycles 3x (3.2x on PAL) faster than cpu
+        let (numerator, denominator) = self.ppu.region.ppu_cpu_clock_ratio();
+        let ppu_clock = self.ppu_clock_remainder + cycles as usize * numerator;
+        self.ppu_clock_remainder = ppu_clock % denominator;
         let nmi_before = self.ppu.nmi_interrupt.is_some();
-        self.ppu.tick(cycles * 3, &mut self.frame);
+        let frame_before = self.ppu.frame_count;
+        self.ppu
+            .tick((ppu_clock / denominator) as u8, &mut self.frame);
         let nmi_after = self.ppu.nmi_interrupt.is_some();
 
         if !nmi_before && nmi_after {
-            (self.gameloop_callback)(&self.ppu, &mut self.joypad, &self.frame);
+            let freezes = self.cheats.freezes().collect::<Vec<_>>();
+            for (addr, value) in freezes {
+                self.poke(addr, value);
+            }
+            (self.gameloop_callback)(&self.ppu, &mut self.ports, &self.frame);
+        }
+        // like fceux, every frame counts, with or without an NMI
+        if self.ppu.frame_count != frame_before {
+            if let (Some(movie), Some(joypad)) = (
+                self.movie.as_mut(),
+                input::player_joypad(&mut self.ports, 0),
+            ) {
+                movie.next_frame(joypad);
+            }
         }
     }
 
-    pub fn poll_nmi_status(&mut self) -> Option<u8> {
+    fn poll_nmi_status(&mut self) -> Option<u8> {
         self.ppu.nmi_interrupt.take()
     }
+
+    fn poll_irq_status(&mut self) -> bool {
+        // nothing drives /IRQ yet (APU frame counter, DMC, mappers)
+        false
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        match addr {
+            RAM..=RAM_MIRROS_END => self.cpu_vram[(addr & 0b0000_0111_1111_1111) as usize],
+            PPU_REGISTERS_START..=PPU_REGISTERS_MIRROR_END => self.ppu.peek(addr),
+            0x4016 => self.ports[0].peek() | CONTROLLER_OPEN_BUS,
+            0x4017 => self.ports[1].peek() | CONTROLLER_OPEN_BUS,
+            0x4000..=0x4015 => 0,
+            0x6000..=0x7FFF => self.prg_ram[(addr - 0x6000) as usize],
+            // NROM has no bank registers, reading the cartridge changes nothing
+            0x8000..=0xFFFF => self.read_cartridge(addr),
+            _ => 0,
+        }
+    }
+
+    fn poke(&mut self, addr: u16, data: u8) {
+        match addr {
+            RAM..=RAM_MIRROS_END => self.cpu_vram[(addr & 0b0000_0111_1111_1111) as usize] = data,
+            0x6000..=0x7FFF => self.prg_ram[(addr - 0x6000) as usize] = data,
+            0x8000..=0xFFFF => {
+                let index = self.prg_rom_index(addr);
+                self.rom.prg_rom[index] = data;
+            }
+            _ => {}
+        }
+    }
+
+    fn cycles(&self) -> usize {
+        self.cycles
+    }
+
+    fn ppu_position(&self) -> Option<(u16, usize)> {
+        Some((self.ppu.scanlines, self.ppu.cycles))
+    }
+
+    fn fetch_instruction(&mut self, addr: u16) {
+        if self.prg_log.is_none() {
+            return;
+        }
+        // the instruction after a JMP ($nnnn) was reached through a pointer
+        let indirect = self
+            .instruction
+            .is_some_and(|(_, opcode)| opcode.code == 0x6c);
+        let opcode = OPCODES[self.peek(addr) as usize];
+        self.instruction = Some((addr, opcode));
+        for i in 0..opcode.bytes_len as u16 {
+            let addr = addr.wrapping_add(i);
+            if addr >= 0x8000 {
+                let flags = if i == 0 && indirect {
+                    cdl::CODE | cdl::INDIRECT_CODE
+                } else {
+                    cdl::CODE
+                };
+                self.log_prg(addr, flags);
+            }
+        }
+    }
+}
+
+impl MemorySpaces for Bus<'_> {
+    fn space_len(&self, space: Space) -> usize {
+        match space {
+            Space::Cpu => 0x10000,
+            Space::Ppu => 0x4000,
+            Space::Oam => self.ppu.oam_data.len(),
+            Space::PrgRom => self.rom.prg_rom.len(),
+            // the ppu's copy, CHR RAM is written there
+            Space::ChrRom => self.ppu.chr_rom.len(),
+        }
+    }
+
+    fn peek_space(&self, space: Space, addr: usize) -> u8 {
+        match space {
+            Space::Cpu => self.peek(addr as u16),
+            Space::Ppu => self.ppu.peek_memory(addr as u16),
+            Space::Oam => self.ppu.oam_data.get(addr).copied().unwrap_or(0),
+            Space::PrgRom => self.rom.prg_rom.get(addr).copied().unwrap_or(0),
+            Space::ChrRom => self.ppu.chr_rom.get(addr).copied().unwrap_or(0),
+        }
+    }
+
+    fn poke_space(&mut self, space: Space, addr: usize, data: u8) {
+        let byte = match space {
+            Space::Cpu => return self.poke(addr as u16, data),
+            Space::Ppu => return self.ppu.poke_memory(addr as u16, data),
+            Space::Oam => self.ppu.oam_data.get_mut(addr),
+            Space::PrgRom => self.rom.prg_rom.get_mut(addr),
+            Space::ChrRom => self.ppu.chr_rom.get_mut(addr),
+        };
+        if let Some(byte) = byte {
+            *byte = data;
+        }
+    }
 }
 
 // const RAM: u16 = 0x0000;
@@ -116,9 +325,9 @@ impl Mem for Bus<'_> {
                 let mirror_down_addr = addr & 0b0000_0111_1111_1111;
                 self.cpu_vram[mirror_down_addr as usize]
             }
-            0x2000 => self.ppu.control_reg.bits(),
-            0x2001 => self.ppu.mask_reg.bits(),
-            0x2003 | 0x2005 | 0x2006 | 0x4014 => 0,
+            // write-only registers return the PPU I/O latch
+            0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 => self.ppu.read_open_bus(),
+            0x4014 => 0,
             0x2002 => self.ppu.read_status(),
             0x2004 => self.ppu.read_oam_data(),
             0x2007 => self.ppu.read_data(),
@@ -127,9 +336,18 @@ impl Mem for Bus<'_> {
                 self.mem_read(_mirror_down_addr)
             }
             0x4000..=0x4015 => 0, // apu
-            0x4016 => self.joypad.read(),
-            0x4017 => 0, // joypad 1 or 2
-            0x6000..=0xFFFF => self.read_prg_rom(addr),
+            0x4016 | 0x4017 => {
+                let device = &mut self.ports[(addr - 0x4016) as usize];
+                device.observe(&self.frame, self.ppu.scanlines);
+                device.read() | CONTROLLER_OPEN_BUS
+            }
+            0x6000..=0x7FFF => self.prg_ram[(addr - 0x6000) as usize],
+            0x8000..=0xFFFF => {
+                if self.prg_log.is_some() {
+                    self.log_prg_read(addr);
+                }
+                self.read_cartridge(addr)
+            }
             _ => {
                 panic!("ignoring mem access at {:x}", addr);
                 0
@@ -140,7 +358,9 @@ impl Mem for Bus<'_> {
         match addr {
             RAM..=RAM_MIRROS_END => {
                 let mirror_down_addr = addr & 0b0000_0111_1111_1111;
-                self.cpu_vram[mirror_down_addr as usize] = data;
+                // a frozen byte keeps its value
+                self.cpu_vram[mirror_down_addr as usize] =
+                    self.cheats.frozen(mirror_down_addr).unwrap_or(data);
             }
             0x2000 => self.ppu.write_to_control_reg(data),
             0x2001 => self.ppu.write_to_mask_reg(data),
@@ -155,17 +375,27 @@ impl Mem for Bus<'_> {
                 self.mem_write(_mirror_down_addr, data);
             }
             0x4000..=0x4013 | 0x4015 => {} // apu
-            0x4016 => self.joypad.write(data),
-            0x4017 => {} // joypad 1 or 2
+            // one strobe line for both ports
+            0x4016 => self.ports.iter_mut().for_each(|device| device.write(data)),
+            0x4017 => {} // apu frame counter
             // https://wiki.nesdev.com/w/index.php/PPU_programmer_reference#OAM_DMA_.28.244014.29_.3E_write
             0x4014 => {
+                // the cpu is halted for 513 cycles (514 when starting on an odd cycle):
+                // one read and one write per byte.
+                let wait = if self.cycles % 2 == 1 { 2 } else { 1 };
+                self.tick(wait);
                 let mut buffer: [u8; 256] = [0; 256];
                 let hi: u16 = (data as u16) << 8;
                 for i in 0..256u16 {
+                    self.tick(1);
                     buffer[i as usize] = self.mem_read(hi + i);
+                    self.tick(1);
                 }
                 self.ppu.write_to_oam_dma(&buffer);
             }
+            0x6000..=0x7FFF => {
+                self.prg_ram[(addr - 0x6000) as usize] = self.cheats.frozen(addr).unwrap_or(data)
+            }
             0x4018..=0xFFFF => {
                 panic!("attempt to write to cartridge rom space, {:x}", addr);
             }
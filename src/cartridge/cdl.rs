@@ -0,0 +1,164 @@
+// code/data log in the FCEUX `.cdl` format: one flag byte per PRG ROM byte
+// followed by one per CHR ROM byte (nothing for CHR RAM).
+// https://fceux.com/web/help/CodeDataLogger.html
+
+// PRG flags, `xPdcAADC`
+pub const CODE: u8 = 0x01;
+pub const DATA: u8 = 0x02;
+// which 8kib window of $8000-$FFFF the byte was last seen in
+pub const BANK_MASK: u8 = 0x0c;
+// target of an indirect jump
+pub const INDIRECT_CODE: u8 = 0x10;
+// read through a `($nn,X)` or `($nn),Y` pointer
+pub const INDIRECT_DATA: u8 = 0x20;
+// played by the DMC. never set: there is no APU, DMC fetches aren't logged
+pub const PCM_DATA: u8 = 0x40;
+
+// CHR flags
+pub const RENDERED: u8 = 0x01;
+pub const READ: u8 = 0x02;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeDataLog {
+    pub prg: Vec<u8>,
+    pub chr: Vec<u8>,
+}
+
+impl CodeDataLog {
+    pub fn new(prg_rom_len: usize, chr_rom_len: usize) -> Self {
+        CodeDataLog {
+            prg: vec![0; prg_rom_len],
+            chr: vec![0; chr_rom_len],
+        }
+    }
+
+    /// read a `.cdl` file of a rom with the given PRG and CHR ROM sizes.
+    pub fn from_bytes(
+        bytes: &[u8],
+        prg_rom_len: usize,
+        chr_rom_len: usize,
+    ) -> Result<CodeDataLog, String> {
+        if bytes.len() != prg_rom_len + chr_rom_len {
+            return Err(format!(
+                "code/data log has {} bytes but the rom has {} of PRG and {} of CHR",
+                bytes.len(),
+                prg_rom_len,
+                chr_rom_len
+            ));
+        }
+        Ok(CodeDataLog {
+            prg: bytes[..prg_rom_len].to_vec(),
+            chr: bytes[prg_rom_len..].to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.prg.as_slice(), self.chr.as_slice()].concat()
+    }
+
+    /// PRG ROM bytes only seen as data, never executed.
+    pub fn is_data(&self, index: usize) -> bool {
+        self.prg
+            .get(index)
+            .is_some_and(|flags| flags & (CODE | DATA) == DATA)
+    }
+}
+
+/// or `flags` into the log entry of the PRG ROM byte at `index`, seen at `addr`.
+pub fn mark_prg(log: &mut [u8], index: usize, addr: u16, flags: u8) {
+    if let Some(entry) = log.get_mut(index) {
+        *entry |= flags | ((addr >> 11) as u8 & BANK_MASK);
+    }
+}
+
+/// or `flags` into the log entries of `len` CHR ROM bytes.
+pub fn mark_chr(log: &mut [u8], start: usize, len: usize, flags: u8) {
+    let end = (start + len).min(log.len());
+    for entry in log.iter_mut().take(end).skip(start) {
+        *entry |= flags;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cartridge::rom::test::test_rom;
+    use crate::cpu_internals::cpu::CPU;
+    use crate::cpu_internals::cpu_bus::CpuBus;
+    use crate::ppu::{NesPPU, PPU};
+    use crate::render;
+    use crate::rendering::frame::Frame;
+
+    #[test]
+    fn test_cdl_file() {
+        let mut log = CodeDataLog::new(4, 2);
+        mark_prg(&mut log.prg, 1, 0xe001, CODE);
+        mark_prg(&mut log.prg, 2, 0x8002, DATA | INDIRECT_DATA);
+        mark_chr(&mut log.chr, 1, 16, RENDERED);
+        let bytes = log.to_bytes();
+        assert_eq!(bytes, [0x00, 0x0d, 0x22, 0x00, 0x00, 0x01]);
+        assert!(log.is_data(2));
+        assert!(!log.is_data(1));
+        assert!(!log.is_data(4));
+
+        assert_eq!(CodeDataLog::from_bytes(&bytes, 4, 2), Ok(log));
+        assert!(CodeDataLog::from_bytes(&bytes, 4, 0).is_err());
+    }
+
+    #[test]
+    fn test_bus_logs_code_and_data() {
+        let mut bus = Bus::new(test_rom(), |_, _, _| {});
+        let log = CodeDataLog::new(bus.rom.prg_rom.len(), bus.rom.chr_rom.len());
+        bus.start_code_data_log(log);
+        // LDA $8010 / LDA ($00),Y / JMP ($0002) / $9000: NOP
+        let program = [0xad, 0x10, 0x80, 0xb1, 0x00, 0x6c, 0x02, 0x00];
+        for (i, data) in program.iter().enumerate() {
+            bus.poke(0x8000 + i as u16, *data);
+        }
+        bus.poke(0x9000, 0xea);
+        bus.poke(0x0000, 0x20);
+        bus.poke(0x0001, 0x80);
+        bus.poke(0x0002, 0x00);
+        bus.poke(0x0003, 0x90);
+
+        let mut cpu = CPU::new(bus);
+        cpu.program_counter = 0x8000;
+        cpu.run_with_callback(|cpu| {
+            if cpu.program_counter == 0x9001 {
+                cpu.halted = true;
+            }
+        })
+        .unwrap();
+        cpu.bus.ppu.read_data();
+
+        let log = cpu.bus.code_data_log().unwrap();
+        assert_eq!(log.prg[0x0000..0x0008], [CODE; 8]);
+        assert_eq!(log.prg[0x0008], 0);
+        assert_eq!(log.prg[0x0010], DATA);
+        assert_eq!(log.prg[0x0020], DATA | INDIRECT_DATA);
+        assert_eq!(log.prg[0x1000], CODE | INDIRECT_CODE);
+        assert_eq!(log.chr[0..2], [READ, 0]);
+    }
+
+    #[test]
+    fn test_only_drawn_tiles_are_rendered() {
+        let mut ppu = NesPPU::new_empty_rom();
+        *ppu.chr_log.get_mut() = Some(vec![0; 2048]);
+        // sprite 0 on lines 16-23 with tile 1, sprite 1 hidden below the screen with tile 2
+        ppu.oam_data[0..8].copy_from_slice(&[0x10, 0x01, 0x00, 0x20, 0xf0, 0x02, 0x00, 0x20]);
+        // sprite 2 further down with tile 3
+        ppu.oam_data[8..12].copy_from_slice(&[0x80, 0x03, 0x00, 0x20]);
+        let mut frame = Frame::new();
+        render::render(&ppu, &mut frame, 24);
+        // nothing while the mask hides the sprites and the background
+        assert_eq!(ppu.chr_log.borrow().as_ref().unwrap()[0..64], [0; 64]);
+
+        ppu.write_to_mask_reg(0b0001_0000);
+        render::render(&ppu, &mut frame, 24);
+        let log = ppu.chr_log.take().unwrap();
+        assert_eq!(log[0], 0);
+        assert_eq!(log[16..32], [RENDERED; 16]);
+        assert_eq!(log[32..64], [0; 32]);
+    }
+}
@@ -1,2 +1,3 @@
+pub mod cdl;
 pub mod mem;
 pub mod rom;
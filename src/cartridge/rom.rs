@@ -1,3 +1,5 @@
+use crate::region::Region;
+
 const NES_TAG: &[u8] = &[0x4e, 0x45, 0x53, 0x1a];
 const PRG_ROM_PAGE_SIZE: usize = 0x4000; //16384
 const CHR_ROM_PAGE_SIZE: usize = 0x2000; // 8192
@@ -15,6 +17,7 @@ pub struct ROM {
     pub chr_rom: Vec<u8>,
     pub mapper: u8,
     pub screen_mirroring: Mirroring,
+    pub region: Region,
 }
 
 impl ROM {
@@ -24,8 +27,13 @@ impl ROM {
         }
         let mapper = (raw[7] & 0b1111_0000) | (raw[6] >> 4);
         let ines_ver = (raw[7] >> 2) & 0b11;
-        if ines_ver != 0 {
-            return Err("NES2.0 format is not supported".to_string());
+        let is_nes2 = match ines_ver {
+            0 => false,
+            2 => true,
+            _ => return Err("unknown iNes header version".to_string()),
+        };
+        if is_nes2 && raw[8] & 0b1111 != 0 {
+            return Err("mapper numbers over 255 are not supported".to_string());
         }
 
         let is_four_screen = raw[6] & 0b1000 != 0;
@@ -36,8 +44,37 @@ impl ROM {
             (false, false) => Mirroring::Horizontal,
         };
 
-        let prg_rom_size = raw[4] as usize * PRG_ROM_PAGE_SIZE;
-        let chr_rom_size = raw[5] as usize * CHR_ROM_PAGE_SIZE;
+        // https://www.nesdev.org/wiki/NES_2.0#PRG-ROM_Area
+        let (prg_rom_pages, chr_rom_pages) = if is_nes2 {
+            let prg_msb = (raw[9] & 0b1111) as usize;
+            let chr_msb = (raw[9] >> 4) as usize;
+            if prg_msb == 0xf || chr_msb == 0xf {
+                return Err("exponent-multiplier rom size is not supported".to_string());
+            }
+            (
+                prg_msb << 8 | raw[4] as usize,
+                chr_msb << 8 | raw[5] as usize,
+            )
+        } else {
+            (raw[4] as usize, raw[5] as usize)
+        };
+        let prg_rom_size = prg_rom_pages * PRG_ROM_PAGE_SIZE;
+        let chr_rom_size = chr_rom_pages * CHR_ROM_PAGE_SIZE;
+
+        let region = if is_nes2 {
+            // https://www.nesdev.org/wiki/NES_2.0#CPU/PPU_Timing
+            match raw[12] & 0b11 {
+                1 => Region::PAL,
+                3 => Region::Dendy,
+                // 2 is multiple-region, prefer NTSC
+                _ => Region::NTSC,
+            }
+        } else if raw[12..16].iter().all(|b| *b == 0) && raw[9] & 0b1 == 1 {
+            // iNes 1.0 flags 9, only trusted when the unused bytes are clean
+            Region::PAL
+        } else {
+            Region::NTSC
+        };
 
         let skip_trainer = raw[6] & 0b100 != 0;
 
@@ -49,6 +86,7 @@ impl ROM {
             chr_rom: raw[chr_rom_start..(chr_rom_start + chr_rom_size)].to_vec(),
             mapper,
             screen_mirroring,
+            region,
         })
     }
 }
@@ -109,6 +147,7 @@ pub mod test {
         assert_eq!(rom.prg_rom, vec!(1; 2 * PRG_ROM_PAGE_SIZE));
         assert_eq!(rom.mapper, 3);
         assert_eq!(rom.screen_mirroring, Mirroring::Vertical);
+        assert_eq!(rom.region, Region::NTSC);
     }
 
     #[test]
@@ -146,10 +185,26 @@ pub mod test {
     }
 
     #[test]
-    fn test_nes2_is_not_supported() {
+    fn test_nes2_region() {
+        let test_rom = create_rom(TestROM {
+            header: vec![
+                0x4E, 0x45, 0x53, 0x1A, 0x01, 0x01, 0x31, 0x8, 00, 00, 00, 00, 0x01, 00, 00, 00,
+            ],
+            trainer: None,
+            pgp_rom: vec![1; 1 * PRG_ROM_PAGE_SIZE],
+            chr_rom: vec![2; 1 * CHR_ROM_PAGE_SIZE],
+        });
+        let rom = ROM::new(&test_rom).unwrap();
+        assert_eq!(rom.prg_rom, vec!(1; 1 * PRG_ROM_PAGE_SIZE));
+        assert_eq!(rom.mapper, 3);
+        assert_eq!(rom.region, Region::PAL);
+    }
+
+    #[test]
+    fn test_nes2_large_mapper_is_not_supported() {
         let test_rom = create_rom(TestROM {
             header: vec![
-                0x4E, 0x45, 0x53, 0x1A, 0x01, 0x01, 0x31, 0x8, 00, 00, 00, 00, 00, 00, 00, 00,
+                0x4E, 0x45, 0x53, 0x1A, 0x01, 0x01, 0x31, 0x8, 0x01, 00, 00, 00, 00, 00, 00, 00,
             ],
             trainer: None,
             pgp_rom: vec![1; 1 * PRG_ROM_PAGE_SIZE],
@@ -158,7 +213,7 @@ pub mod test {
         let rom = ROM::new(&test_rom);
         match rom {
             Result::Ok(_) => assert!(false, "should not load rom"),
-            Result::Err(str) => assert_eq!(str, "NES2.0 format is not supported"),
+            Result::Err(str) => assert_eq!(str, "mapper numbers over 255 are not supported"),
         }
     }
 }
@@ -0,0 +1,344 @@
+use crate::cpu_internals::cpu_bus::CpuBus;
+use crate::debugger::breakpoint::parse_number;
+use crate::debugger::memory::format_addresses;
+
+// the 16 letters of a Game Genie code, each one a 4 bit value
+const GENIE_LETTERS: &str = "APZLGITYEOXUKSVN";
+
+// a Game Genie code replaces what the cpu reads from the cartridge at `addr`,
+// 8 letter codes only when the ROM has `compare` there (bank switched games).
+// https://www.nesdev.org/wiki/Game_Genie
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameGenie {
+    pub addr: u16,
+    pub value: u8,
+    pub compare: Option<u8>,
+}
+
+impl GameGenie {
+    pub fn decode(code: &str) -> Result<GameGenie, String> {
+        let n = code
+            .chars()
+            .map(|c| {
+                GENIE_LETTERS
+                    .find(c.to_ascii_uppercase())
+                    .map(|value| value as u16)
+                    .ok_or(format!("{} is not a Game Genie letter in {}", c, code))
+            })
+            .collect::<Result<Vec<u16>, String>>()?;
+        if n.len() != 6 && n.len() != 8 {
+            return Err(format!("Game Genie code {} must have 6 or 8 letters", code));
+        }
+        let addr = 0x8000
+            | (n[3] & 7) << 12
+            | (n[5] & 7) << 8
+            | (n[4] & 8) << 8
+            | (n[2] & 7) << 4
+            | (n[1] & 8) << 4
+            | (n[4] & 7)
+            | (n[3] & 8);
+        let value = (n[1] & 7) << 4 | (n[0] & 8) << 4 | (n[0] & 7);
+        if n.len() == 6 {
+            return Ok(GameGenie {
+                addr,
+                value: (value | (n[5] & 8)) as u8,
+                compare: None,
+            });
+        }
+        let compare = (n[7] & 7) << 4 | (n[6] & 8) << 4 | (n[6] & 7) | (n[5] & 8);
+        Ok(GameGenie {
+            addr,
+            value: (value | (n[7] & 8)) as u8,
+            compare: Some(compare as u8),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cheat {
+    Genie(GameGenie),
+    // Pro Action Replay: `value` is written to RAM at `addr` every frame
+    Freeze { addr: u16, value: u8 },
+}
+
+impl Cheat {
+    /// a Game Genie code like `SXIOPO`, or a Pro Action Replay code like
+    /// `0075:09` or `007509` (address then value, in hex).
+    pub fn parse(code: &str) -> Result<Cheat, String> {
+        let code = code.trim();
+        let is_genie = code
+            .chars()
+            .all(|c| GENIE_LETTERS.contains(c.to_ascii_uppercase()));
+        if is_genie {
+            return GameGenie::decode(code).map(Cheat::Genie);
+        }
+        let hex = code.replace(':', "");
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "invalid cheat {}, expected a Game Genie code or AAAA:VV",
+                code
+            ));
+        }
+        let addr = u16::from_str_radix(&hex[..4], 16).map_err(|e| e.to_string())?;
+        let value = u8::from_str_radix(&hex[4..], 16).map_err(|e| e.to_string())?;
+        match addr {
+            // the mirrors of the internal RAM freeze the same byte
+            0x0000..=0x1fff => Ok(Cheat::Freeze {
+                addr: addr & 0x07ff,
+                value,
+            }),
+            0x6000..=0x7fff => Ok(Cheat::Freeze { addr, value }),
+            _ => Err(format!(
+                "can't freeze ${:04X}, only RAM at $0000-$1FFF and $6000-$7FFF",
+                addr
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Cheats {
+    cheats: Vec<Cheat>,
+}
+
+impl Cheats {
+    pub fn new() -> Self {
+        Cheats::default()
+    }
+
+    pub fn add(&mut self, cheat: Cheat) {
+        self.cheats.push(cheat);
+    }
+
+    pub fn clear(&mut self) {
+        self.cheats.clear();
+    }
+
+    pub fn list(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    /// what the cpu reads at `addr` of the cartridge, where the ROM has `value`.
+    pub fn read_rom(&self, addr: u16, value: u8) -> u8 {
+        self.cheats
+            .iter()
+            .find_map(|cheat| match cheat {
+                Cheat::Genie(genie)
+                    if genie.addr == addr && genie.compare.is_none_or(|c| c == value) =>
+                {
+                    Some(genie.value)
+                }
+                _ => None,
+            })
+            .unwrap_or(value)
+    }
+
+    /// the frozen value of `addr`, if any.
+    pub fn frozen(&self, addr: u16) -> Option<u8> {
+        self.cheats.iter().find_map(|cheat| match cheat {
+            Cheat::Freeze { addr: a, value } if *a == addr => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn freezes(&self) -> impl Iterator<Item = (u16, u8)> + '_ {
+        self.cheats.iter().filter_map(|cheat| match cheat {
+            Cheat::Freeze { addr, value } => Some((*addr, *value)),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    // equal to a value
+    Equal(u8),
+    // compared with the last snapshot
+    Greater,
+    Less,
+    Changed,
+    Unchanged,
+}
+
+impl Filter {
+    /// `= 3`, `>`, `<`, `changed` or `same`
+    pub fn parse(args: &[&str]) -> Result<Filter, String> {
+        match args {
+            ["=", value] => parse_number(value)
+                .and_then(|value| {
+                    u8::try_from(value).map_err(|_| format!("{} is not a byte", value))
+                })
+                .map(Filter::Equal),
+            [">"] => Ok(Filter::Greater),
+            ["<"] => Ok(Filter::Less),
+            ["changed"] => Ok(Filter::Changed),
+            ["same"] => Ok(Filter::Unchanged),
+            _ => Err(format!(
+                "invalid search filter {}, expected = <value>, >, <, changed or same",
+                args.join(" ")
+            )),
+        }
+    }
+
+    fn keeps(&self, old: u8, new: u8) -> bool {
+        match self {
+            Filter::Equal(value) => new == *value,
+            Filter::Greater => new > old,
+            Filter::Less => new < old,
+            Filter::Changed => new != old,
+            Filter::Unchanged => new == old,
+        }
+    }
+}
+
+// narrows down where a game keeps a value (lives, time, ...): start with all
+// of the RAM and filter it after playing a bit, until few addresses are left.
+#[derive(Debug)]
+pub struct RamSearch {
+    // candidate addresses with their value at the last snapshot
+    candidates: Vec<(u16, u8)>,
+}
+
+impl RamSearch {
+    /// internal RAM and the cartridge's, $0000-$07FF and $6000-$7FFF.
+    pub fn new<M: CpuBus>(bus: &M) -> Self {
+        let candidates = (0x0000..0x0800)
+            .chain(0x6000..0x8000)
+            .map(|addr| (addr, bus.peek(addr)))
+            .collect();
+        RamSearch { candidates }
+    }
+
+    /// keep the candidates matching `filter` and take a new snapshot.
+    pub fn filter<M: CpuBus>(&mut self, bus: &M, filter: Filter) {
+        self.candidates = self
+            .candidates
+            .iter()
+            .map(|(addr, old)| (*addr, *old, bus.peek(*addr)))
+            .filter(|(_, old, new)| filter.keeps(*old, *new))
+            .map(|(addr, _, new)| (addr, new))
+            .collect();
+    }
+
+    pub fn candidates(&self) -> &[(u16, u8)] {
+        &self.candidates
+    }
+}
+
+/// `2 candidates: $0075=03 $07A0=03`
+pub fn format_candidates(candidates: &[(u16, u8)]) -> String {
+    let listed = candidates
+        .iter()
+        .map(|(addr, value)| format!("${:04X}={:02X}", addr, value))
+        .collect::<Vec<String>>();
+    format_addresses(&listed, "candidate", "candidates")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cartridge::mem::Mem;
+    use crate::cartridge::rom::test::test_rom;
+    use crate::cpu_internals::cpu_bus::FlatBus;
+
+    #[test]
+    fn test_game_genie() {
+        assert_eq!(
+            GameGenie::decode("GOSSIP"),
+            Ok(GameGenie {
+                addr: 0xd1dd,
+                value: 0x14,
+                compare: None
+            })
+        );
+        assert_eq!(
+            GameGenie::decode("zexpyget"),
+            Ok(GameGenie {
+                addr: 0x94a7,
+                value: 0x02,
+                compare: Some(0xe0)
+            })
+        );
+        assert!(GameGenie::decode("GOSSI").is_err());
+        assert!(GameGenie::decode("GOSSIB").is_err());
+    }
+
+    #[test]
+    fn test_parse_cheat() {
+        assert_eq!(
+            Cheat::parse("0075:09"),
+            Ok(Cheat::Freeze {
+                addr: 0x75,
+                value: 0x09
+            })
+        );
+        assert_eq!(Cheat::parse("6000ff"), Cheat::parse("6000:FF"));
+        assert!(matches!(Cheat::parse("SXIOPO"), Ok(Cheat::Genie(_))));
+        assert_eq!(Cheat::parse("0875:09"), Cheat::parse("0075:09"));
+        assert!(Cheat::parse("8000:00").is_err());
+        assert!(Cheat::parse("2000:00").is_err());
+        assert!(Cheat::parse("75:09").is_err());
+    }
+
+    #[test]
+    fn test_cheats() {
+        let mut cheats = Cheats::new();
+        cheats.add(Cheat::parse("GOSSIP").unwrap());
+        cheats.add(Cheat::parse("ZEXPYGET").unwrap());
+        cheats.add(Cheat::parse("0075:09").unwrap());
+        assert_eq!(cheats.read_rom(0xd1dd, 0xea), 0x14);
+        // the compare value has to match
+        assert_eq!(cheats.read_rom(0x94a7, 0xe0), 0x02);
+        assert_eq!(cheats.read_rom(0x94a7, 0x04), 0x04);
+        assert_eq!(cheats.read_rom(0x8000, 0x42), 0x42);
+        assert_eq!(cheats.frozen(0x75), Some(0x09));
+        assert_eq!(cheats.frozen(0x76), None);
+        cheats.clear();
+        assert_eq!(cheats.read_rom(0xd1dd, 0xea), 0xea);
+    }
+
+    #[test]
+    fn test_bus_cheats() {
+        let mut bus = Bus::new(test_rom(), |_, _, _| {});
+        bus.cheats.add(Cheat::Genie(GameGenie {
+            addr: 0x8000,
+            value: 0xea,
+            compare: Some(1),
+        }));
+        bus.cheats.add(Cheat::parse("0875:09").unwrap());
+        assert_eq!(bus.mem_read(0x8000), 0xea);
+        assert_eq!(bus.peek(0x8000), 0xea);
+        assert_eq!(bus.mem_read(0x8001), 1);
+        bus.mem_write(0x0075, 0x03);
+        assert_eq!(bus.mem_read(0x0075), 0x09);
+        bus.mem_write(0x0076, 0x03);
+        assert_eq!(bus.mem_read(0x0076), 0x03);
+    }
+
+    #[test]
+    fn test_ram_search() {
+        let mut bus = FlatBus::new();
+        bus.poke(0x0075, 3);
+        bus.poke(0x07a0, 3);
+        bus.poke(0x6010, 3);
+        let mut search = RamSearch::new(&bus);
+        assert_eq!(search.candidates().len(), 0x2800);
+        search.filter(&bus, Filter::parse(&["=", "3"]).unwrap());
+        assert_eq!(
+            format_candidates(search.candidates()),
+            "3 candidates: $0075=03 $07A0=03 $6010=03"
+        );
+        // a life lost
+        bus.poke(0x0075, 2);
+        search.filter(&bus, Filter::Less);
+        assert_eq!(
+            format_candidates(search.candidates()),
+            "1 candidate: $0075=02"
+        );
+        search.filter(&bus, Filter::parse(&["changed"]).unwrap());
+        assert_eq!(format_candidates(search.candidates()), "no candidate");
+        assert!(Filter::parse(&["=", "300"]).is_err());
+        assert!(Filter::parse(&["!"]).is_err());
+    }
+}
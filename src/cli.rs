@@ -0,0 +1,293 @@
+use crate::cheats::Cheat;
+use crate::cpu_internals::jam::JamPolicy;
+use crate::input::PortDevice;
+use crate::region::Region;
+use crate::rendering::overscan::Overscan;
+
+// usage: rust-nes [rom.nes] [--overscan none|ntsc|pal|top,bottom,left,right]
+//                 [--region ntsc|pal|dendy] [--jam halt|reset|error] [--test-rom]
+//                 [--trace file|-] [--debug] [--gdb port]
+//                 [--symbols file.dbg|file.nl|file.mlb]... [--disasm] [--cdl file.cdl]
+//                 [--patterns] [--nametables] [--oam] [--cheat code]...
+//                 [--record movie.fm2 | --play movie.fm2]
+//                 [--port2 pad|zapper|vaus|powerpad|none | --four-score]
+#[derive(Debug)]
+pub struct Options {
+    pub rom_path: String,
+    // defaults to the region's overscan
+    pub overscan: Option<Overscan>,
+    // defaults to the region in the rom header
+    pub region: Option<Region>,
+    pub jam_policy: JamPolicy,
+    // run the rom headless as a blargg style test rom and exit with its result
+    pub test_rom: bool,
+    // write a trace line per instruction to this file, `-` for stdout
+    pub trace: Option<String>,
+    // start stopped in the debugger prompt
+    pub debug: bool,
+    // wait for a gdb remote client on this localhost port
+    pub gdb_port: Option<u16>,
+    // label files for the trace, the debugger and the disassembly
+    pub symbols: Vec<String>,
+    // print the disassembly of the PRG ROM and exit
+    pub disasm: bool,
+    // code/data log, updated by the run and used by the disassembly
+    pub cdl: Option<String>,
+    // open the pattern table viewer next to the game
+    pub patterns: bool,
+    // open the nametable viewer next to the game
+    pub nametables: bool,
+    // open the sprite inspector next to the game
+    pub oam: bool,
+    // Game Genie codes and RAM freezes
+    pub cheats: Vec<Cheat>,
+    // record the joypad from power-on into this fm2 movie
+    pub record: Option<String>,
+    // feed the joypad from this fm2 movie
+    pub play: Option<String>,
+    // what is plugged in the second controller port
+    pub port2: PortDevice,
+    // a Four Score in both ports, for 4 players
+    pub four_score: bool,
+}
+
+impl Options {
+    pub fn parse<I>(args: I) -> Result<Options, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut options = Options {
+            rom_path: String::from("nestest.nes"),
+            overscan: None,
+            region: None,
+            jam_policy: JamPolicy::Halt,
+            test_rom: false,
+            trace: None,
+            debug: false,
+            gdb_port: None,
+            symbols: vec![],
+            disasm: false,
+            cdl: None,
+            patterns: false,
+            nametables: false,
+            oam: false,
+            cheats: vec![],
+            record: None,
+            play: None,
+            port2: PortDevice::Pad,
+            four_score: false,
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+            match name.as_str() {
+                "--overscan" => {
+                    let value = Options::value(&name, inline_value, &mut args)?;
+                    options.overscan = Some(Overscan::parse(&value)?);
+                }
+                "--region" => {
+                    let value = Options::value(&name, inline_value, &mut args)?;
+                    options.region = Some(Region::parse(&value)?);
+                }
+                "--jam" => {
+                    let value = Options::value(&name, inline_value, &mut args)?;
+                    options.jam_policy = JamPolicy::parse(&value)?;
+                }
+                "--trace" => {
+                    options.trace = Some(Options::value(&name, inline_value, &mut args)?);
+                }
+                "--test-rom" => options.test_rom = true,
+                "--debug" => options.debug = true,
+                "--disasm" => options.disasm = true,
+                "--patterns" => options.patterns = true,
+                "--nametables" => options.nametables = true,
+                "--oam" => options.oam = true,
+                "--four-score" => options.four_score = true,
+                "--port2" => {
+                    let value = Options::value(&name, inline_value, &mut args)?;
+                    options.port2 = PortDevice::parse(&value)?;
+                }
+                "--cdl" => {
+                    options.cdl = Some(Options::value(&name, inline_value, &mut args)?);
+                }
+                "--cheat" => {
+                    let value = Options::value(&name, inline_value, &mut args)?;
+                    options.cheats.push(Cheat::parse(&value)?);
+                }
+                "--record" => {
+                    options.record = Some(Options::value(&name, inline_value, &mut args)?);
+                }
+                "--play" => {
+                    options.play = Some(Options::value(&name, inline_value, &mut args)?);
+                }
+                "--symbols" => {
+                    let value = Options::value(&name, inline_value, &mut args)?;
+                    options.symbols.push(value);
+                }
+                "--gdb" => {
+                    let value = Options::value(&name, inline_value, &mut args)?;
+                    let port = value
+                        .parse::<u16>()
+                        .map_err(|e| format!("invalid gdb port {}: {}", value, e))?;
+                    options.gdb_port = Some(port);
+                }
+                _ if name.starts_with("--") => return Err(format!("unknown option {}", name)),
+                _ => options.rom_path = arg,
+            }
+        }
+        if options.debug && options.gdb_port.is_some() {
+            return Err("--debug and --gdb can't be used together".to_string());
+        }
+        if options.record.is_some() && options.play.is_some() {
+            return Err("--record and --play can't be used together".to_string());
+        }
+        if options.four_score && options.port2 != PortDevice::Pad {
+            return Err("the Four Score takes both ports, --port2 can't be used".to_string());
+        }
+        Ok(options)
+    }
+
+    fn value<I>(name: &str, inline_value: Option<String>, args: &mut I) -> Result<String, String>
+    where
+        I: Iterator<Item = String>,
+    {
+        inline_value
+            .or_else(|| args.next())
+            .ok_or(format!("option {} needs a value", name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_default_options() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.rom_path, "nestest.nes");
+        assert_eq!(options.overscan, None);
+        assert_eq!(options.region, None);
+        assert_eq!(options.jam_policy, JamPolicy::Halt);
+        assert!(!options.test_rom);
+        assert_eq!(options.trace, None);
+        assert!(!options.debug);
+        assert_eq!(options.gdb_port, None);
+        assert!(options.symbols.is_empty());
+        assert!(!options.disasm);
+        assert_eq!(options.cdl, None);
+        assert!(!options.patterns);
+        assert!(!options.nametables);
+        assert!(!options.oam);
+        assert!(options.cheats.is_empty());
+        assert_eq!(options.record, None);
+        assert_eq!(options.play, None);
+        assert_eq!(options.port2, PortDevice::Pad);
+        assert!(!options.four_score);
+    }
+
+    #[test]
+    fn test_overscan_option() {
+        let options = parse(&["game.nes", "--overscan", "none"]).unwrap();
+        assert_eq!(options.rom_path, "game.nes");
+        assert_eq!(options.overscan, Some(Overscan::none()));
+
+        let options = parse(&["--overscan=0,0,8,8", "game.nes"]).unwrap();
+        assert_eq!(options.overscan, Some(Overscan::new(0, 0, 8, 8)));
+
+        assert!(parse(&["--overscan"]).is_err());
+        assert!(parse(&["--unknown"]).is_err());
+    }
+
+    #[test]
+    fn test_region_option() {
+        let options = parse(&["--region", "pal", "game.nes"]).unwrap();
+        assert_eq!(options.region, Some(Region::PAL));
+        assert!(parse(&["--region=secam"]).is_err());
+    }
+
+    #[test]
+    fn test_jam_option() {
+        let options = parse(&["--jam=error"]).unwrap();
+        assert_eq!(options.jam_policy, JamPolicy::Error);
+        assert!(parse(&["--jam", "ignore"]).is_err());
+    }
+
+    #[test]
+    fn test_test_rom_option() {
+        let options = parse(&["--test-rom", "instr_test.nes"]).unwrap();
+        assert!(options.test_rom);
+        assert_eq!(options.rom_path, "instr_test.nes");
+        assert!(parse(&["--debug"]).unwrap().debug);
+        assert!(parse(&["--patterns"]).unwrap().patterns);
+        assert!(parse(&["--nametables"]).unwrap().nametables);
+        assert!(parse(&["--oam"]).unwrap().oam);
+    }
+
+    #[test]
+    fn test_trace_option() {
+        let options = parse(&["--trace", "-", "game.nes"]).unwrap();
+        assert_eq!(options.trace.as_deref(), Some("-"));
+        let options = parse(&["--trace=trace.log"]).unwrap();
+        assert_eq!(options.trace.as_deref(), Some("trace.log"));
+        assert!(parse(&["--trace"]).is_err());
+    }
+
+    #[test]
+    fn test_gdb_option() {
+        let options = parse(&["--gdb", "6502"]).unwrap();
+        assert_eq!(options.gdb_port, Some(6502));
+        assert!(parse(&["--gdb=port"]).is_err());
+        assert!(parse(&["--gdb=6502", "--debug"]).is_err());
+    }
+
+    #[test]
+    fn test_symbols_option() {
+        let options = parse(&[
+            "--symbols",
+            "game.dbg",
+            "--symbols=game.nes.ram.nl",
+            "--disasm",
+        ])
+        .unwrap();
+        assert_eq!(options.symbols, ["game.dbg", "game.nes.ram.nl"]);
+        assert!(options.disasm);
+
+        let options = parse(&["--cdl", "game.cdl", "--disasm"]).unwrap();
+        assert_eq!(options.cdl.as_deref(), Some("game.cdl"));
+        assert!(parse(&["--cdl"]).is_err());
+    }
+
+    #[test]
+    fn test_cheat_option() {
+        let options = parse(&["--cheat", "SXIOPO", "--cheat=0075:09"]).unwrap();
+        assert_eq!(options.cheats.len(), 2);
+        assert_eq!(options.cheats[1], Cheat::parse("0075:09").unwrap());
+        assert!(parse(&["--cheat", "SXIOP"]).is_err());
+    }
+
+    #[test]
+    fn test_movie_options() {
+        let options = parse(&["--record", "run.fm2"]).unwrap();
+        assert_eq!(options.record.as_deref(), Some("run.fm2"));
+        let options = parse(&["--play=run.fm2"]).unwrap();
+        assert_eq!(options.play.as_deref(), Some("run.fm2"));
+        assert!(parse(&["--play"]).is_err());
+        assert!(parse(&["--record=a.fm2", "--play=b.fm2"]).is_err());
+    }
+
+    #[test]
+    fn test_port_options() {
+        let options = parse(&["--port2", "vaus"]).unwrap();
+        assert_eq!(options.port2, PortDevice::Vaus);
+        assert!(parse(&["--four-score"]).unwrap().four_score);
+        assert!(parse(&["--port2=mouse"]).is_err());
+        assert!(parse(&["--port2=zapper", "--four-score"]).is_err());
+    }
+}
@@ -1,12 +1,20 @@
-use crate::bus::Bus;
 use crate::cartridge::mem::*;
-use crate::cartridge::rom::*;
+use crate::cpu_internals::cpu_bus::{CpuBus, FlatBus};
 use crate::cpu_internals::flags::*;
+use crate::cpu_internals::interrupt::{Interrupt, RESET_VECTOR};
+use crate::cpu_internals::jam::JamPolicy;
 use crate::cpu_internals::opscodes::*;
 
+mod dispatch;
+
 const STACK_BASE: u16 = 0x0100;
+// unstable opcodes mix A with a chip dependent constant
+const XAA_MAGIC: u8 = 0xee;
+const LXA_MAGIC: u8 = 0xff;
 
-pub struct CPU<'a> {
+// the 6502 core, generic over the bus it runs on:
+// the NES `Bus`, or a `FlatBus` for tests and plain 6502 programs.
+pub struct CPU<M> {
     pub register_a: u8,
     pub register_x: u8,
     pub register_y: u8,
@@ -14,14 +22,18 @@ pub struct CPU<'a> {
     pub status: StatusFlags,
     pub program_counter: u16,
     pub stack_pointer: u8,
-    pub bus: Bus<'a>,
-
-    memory: [u8; 0xffff],
+    pub bus: M,
+    // I flag as seen by the interrupt poll, one instruction behind for CLI/SEI/PLP
+    pub(crate) irq_inhibit: bool,
+    // set (e.g. from the run callback) to return from `run_with_callback`
+    pub halted: bool,
+    pub jam_policy: JamPolicy,
+    // stuck on a JAM opcode until reset
+    pub jammed: bool,
 }
 
-impl<'a> CPU<'a> {
-    #[allow(dead_code)]
-    pub fn new<'b>(bus: Bus<'b>) -> CPU<'b> {
+impl<M: CpuBus> CPU<M> {
+    pub fn new(bus: M) -> CPU<M> {
         CPU {
             register_a: 0,
             register_x: 0,
@@ -30,53 +42,57 @@ impl<'a> CPU<'a> {
             program_counter: 0,
             stack_pointer: 0,
             bus,
-            memory: [0; 0xffff],
+            irq_inhibit: true,
+            halted: false,
+            jam_policy: JamPolicy::Halt,
+            jammed: false,
         }
     }
 }
-impl<'a> AddressingModeConverter for CPU<'a> {
+impl<M: CpuBus> AddressingModeConverter for CPU<M> {
+    // resolve the operand address of the instruction at `addr` without spending cpu cycles.
     fn get_absolute_address(&mut self, mode: &AddressingMode, addr: u16) -> (u16, bool) {
         match mode {
-            AddressingMode::ZeroPage => (self.mem_read(addr) as u16, false),
-            AddressingMode::Absolute => (self.mem_read_u16(addr), false),
+            AddressingMode::ZeroPage => (self.bus.mem_read(addr) as u16, false),
+            AddressingMode::Absolute => (self.bus.mem_read_u16(addr), false),
             AddressingMode::ZeroPage_X => {
-                let pos = self.mem_read(addr);
+                let pos = self.bus.mem_read(addr);
                 let addr = pos.wrapping_add(self.register_x) as u16;
                 return (addr, false);
             }
             AddressingMode::ZeroPage_Y => {
-                let pos = self.mem_read(addr);
+                let pos = self.bus.mem_read(addr);
                 let addr = pos.wrapping_add(self.register_y) as u16;
                 return (addr, false);
             }
             AddressingMode::Absolute_X => {
-                let base = self.mem_read_u16(addr);
+                let base = self.bus.mem_read_u16(addr);
                 let addr = base.wrapping_add(self.register_x as u16);
                 return (addr, self.page_cross(base, addr));
             }
             AddressingMode::Absolute_Y => {
-                let base = self.mem_read_u16(addr);
+                let base = self.bus.mem_read_u16(addr);
                 let addr = base.wrapping_add(self.register_y as u16);
                 return (addr, self.page_cross(base, addr));
             }
             AddressingMode::Indirect_X => {
-                let base = self.mem_read(addr);
+                let base = self.bus.mem_read(addr);
                 let ptr: u8 = (base as u8).wrapping_add(self.register_x);
-                let lo = self.mem_read(ptr as u16);
-                let hi = self.mem_read(ptr.wrapping_add(1) as u16);
+                let lo = self.bus.mem_read(ptr as u16);
+                let hi = self.bus.mem_read(ptr.wrapping_add(1) as u16);
                 let result = (hi as u16) << 8 | (lo as u16);
                 return (result, false);
             }
             AddressingMode::Indirect_Y => {
-                let base = self.mem_read(addr);
-                let lo = self.mem_read(base as u16);
-                let hi = self.mem_read(base.wrapping_add(1) as u16);
+                let base = self.bus.mem_read(addr);
+                let lo = self.bus.mem_read(base as u16);
+                let hi = self.bus.mem_read(base.wrapping_add(1) as u16);
                 let deref_base = (hi as u16) << 8 | (lo as u16);
                 let deref = deref_base.wrapping_add(self.register_y as u16);
                 return (deref, self.page_cross(deref, deref_base));
             }
             AddressingMode::Relative => {
-                let base = self.mem_read_u16(self.program_counter);
+                let base = self.bus.mem_read_u16(self.program_counter);
                 return (
                     self.program_counter.wrapping_add(base),
                     self.page_cross(self.program_counter.wrapping_add(base), base),
@@ -85,584 +101,211 @@ impl<'a> AddressingModeConverter for CPU<'a> {
             _ => panic!("mode {:?} is not supported", mode),
         }
     }
+    // fetch the operand address like the 6502 does, one cycle per bus access.
+    // the dummy read of indexed modes on a page cross is left to the caller
+    // since it depends on the instruction being a read or a write.
     fn get_operand_address(&mut self, mode: &AddressingMode) -> (u16, bool) {
+        let pc = self.program_counter;
         match mode {
-            AddressingMode::Immediate => (self.program_counter, false),
-            _ => self.get_absolute_address(mode, self.program_counter),
+            AddressingMode::Immediate => (pc, false),
+            AddressingMode::ZeroPage => (self.mem_read(pc) as u16, false),
+            AddressingMode::Absolute => (self.mem_read_u16(pc), false),
+            AddressingMode::ZeroPage_X => {
+                let pos = self.mem_read(pc);
+                // the cpu reads the base address while adding the index
+                self.dummy_read(pos as u16);
+                (pos.wrapping_add(self.register_x) as u16, false)
+            }
+            AddressingMode::ZeroPage_Y => {
+                let pos = self.mem_read(pc);
+                self.dummy_read(pos as u16);
+                (pos.wrapping_add(self.register_y) as u16, false)
+            }
+            AddressingMode::Absolute_X => {
+                let base = self.mem_read_u16(pc);
+                let addr = base.wrapping_add(self.register_x as u16);
+                (addr, self.page_cross(base, addr))
+            }
+            AddressingMode::Absolute_Y => {
+                let base = self.mem_read_u16(pc);
+                let addr = base.wrapping_add(self.register_y as u16);
+                (addr, self.page_cross(base, addr))
+            }
+            AddressingMode::Indirect_X => {
+                let base = self.mem_read(pc);
+                self.dummy_read(base as u16);
+                let ptr: u8 = base.wrapping_add(self.register_x);
+                let lo = self.mem_read(ptr as u16);
+                let hi = self.mem_read(ptr.wrapping_add(1) as u16);
+                ((hi as u16) << 8 | (lo as u16), false)
+            }
+            AddressingMode::Indirect_Y => {
+                let base = self.mem_read(pc);
+                let lo = self.mem_read(base as u16);
+                let hi = self.mem_read(base.wrapping_add(1) as u16);
+                let deref_base = (hi as u16) << 8 | (lo as u16);
+                let deref = deref_base.wrapping_add(self.register_y as u16);
+                (deref, self.page_cross(deref, deref_base))
+            }
+            _ => panic!("mode {:?} is not supported", mode),
         }
     }
 }
 
 // Memory
-impl Mem for CPU<'_> {
+// every access takes one cpu cycle, the rest of the system is clocked from here
+// so the PPU (and later APU/mappers) see each read and write on the right cycle.
+impl<M: CpuBus> Mem for CPU<M> {
     fn mem_read(&mut self, addr: u16) -> u8 {
+        self.bus.tick(1);
         self.bus.mem_read(addr)
     }
     fn mem_write(&mut self, addr: u16, data: u8) {
+        self.bus.tick(1);
         self.bus.mem_write(addr, data);
     }
-    fn mem_read_u16(&mut self, pos: u16) -> u16 {
-        self.bus.mem_read_u16(pos)
-    }
-    fn mem_write_u16(&mut self, pos: u16, data: u16) {
-        self.bus.mem_write_u16(pos, data);
-    }
 }
 
-impl CPU<'_> {
+impl<M: CpuBus> CPU<M> {
     fn page_cross(&self, addr1: u16, addr2: u16) -> bool {
         addr1 & 0xFF00 != addr2 & 0xFF00
     }
-    pub fn load_and_run(&mut self, program: Vec<u8>) {
-        // launch or inserting new cartridge, then reset program ROM address state.
-        self.load(program);
-        self.reset();
-        self.run();
+    // a bus cycle whose value is thrown away (internal operations of the 6502)
+    fn dummy_read(&mut self, addr: u16) {
+        self.mem_read(addr);
     }
-    // pub fn load(&mut self, program: Vec<u8>) {
-    //     self.memory[0x8000..(0x8000 + program.len())].copy_from_slice(&program[..]);
-    //     // 0xFFFC: program counter address set start point.
-    //     self.mem_write_u16(0xFFFC, 0x8000);
-    // }
-    pub fn load(&mut self, program: Vec<u8>) {
-        self.memory[0x0600..(0x0600 + program.len())].copy_from_slice(&program);
-        self.mem_write_u16(0xfffc, 0x0600);
+    // spend cycles until the ppu starts a new frame, a single one without a ppu
+    fn idle_frame(&mut self) {
+        let mut scanline = self.bus.ppu_position().map(|(line, _)| line);
+        loop {
+            self.dummy_read(0xffff);
+            match (scanline, self.bus.ppu_position()) {
+                (Some(previous), Some((line, _))) if line >= previous => scanline = Some(line),
+                _ => return,
+            }
+        }
     }
-
-    fn interrupt_nmi(&mut self) {
-        self.stack_push_u16(self.program_counter);
-        let mut flag = self.status.clone();
-        flag.set(StatusFlags::BREAK, false);
-        flag.set(StatusFlags::RESERVED, true);
-
-        self.stack_push(flag.bits());
-        self.status.insert(StatusFlags::INTERRUPT_DISABLE);
-        self.bus.tick(2);
-        self.program_counter = self.mem_read_u16(0xfffa);
+    // operand of a read instruction.
+    // crossing a page first reads from the address with the un-fixed high byte.
+    fn read_operand(&mut self, mode: &AddressingMode) -> u8 {
+        let (addr, page_cross) = self.get_operand_address(mode);
+        if page_cross {
+            self.dummy_read(addr.wrapping_sub(0x100));
+        }
+        self.mem_read(addr)
     }
-
-    pub fn run_with_callback<F>(&mut self, mut callback: F)
+    // operand address of a write or read-modify-write instruction.
+    // indexed modes always spend the extra cycle, page cross or not.
+    fn write_operand_address(&mut self, mode: &AddressingMode) -> u16 {
+        let (addr, page_cross) = self.get_operand_address(mode);
+        match mode {
+            AddressingMode::Absolute_X
+            | AddressingMode::Absolute_Y
+            | AddressingMode::Indirect_Y => {
+                let uncorrected = if page_cross {
+                    addr.wrapping_sub(0x100)
+                } else {
+                    addr
+                };
+                self.dummy_read(uncorrected);
+            }
+            _ => {}
+        }
+        addr
+    }
+    // read-modify-write instructions write the unmodified value back
+    // before writing the result.
+    fn read_modify_write_operand(&mut self, mode: &AddressingMode) -> (u16, u8) {
+        let addr = self.write_operand_address(mode);
+        let value = self.mem_read(addr);
+        self.mem_write(addr, value);
+        (addr, value)
+    }
+    // SHX, SHY, AHX and TAS store `value & (H + 1)`, H being the high byte of the base address.
+    // when the index crosses a page, the stored value also replaces the high byte of the target.
+    fn unstable_store(&mut self, mode: &AddressingMode, value: u8) {
+        let (addr, page_cross) = self.get_operand_address(mode);
+        let (base_hi, uncorrected) = if page_cross {
+            (
+                ((addr >> 8) as u8).wrapping_sub(1),
+                addr.wrapping_sub(0x100),
+            )
+        } else {
+            ((addr >> 8) as u8, addr)
+        };
+        self.dummy_read(uncorrected);
+        let data = value & base_hi.wrapping_add(1);
+        let addr = if page_cross {
+            (data as u16) << 8 | (addr & 0xff)
+        } else {
+            addr
+        };
+        self.mem_write(addr, data);
+    }
+    pub fn run_with_callback<F>(&mut self, mut callback: F) -> Result<(), String>
     where
-        F: FnMut(&mut CPU),
+        F: FnMut(&mut CPU<M>),
     {
+        self.halted = false;
         loop {
-            if let Some(_nmi) = self.bus.poll_nmi_status() {
-                self.interrupt_nmi();
+            if !self.jammed {
+                if let Some(interrupt) = self.poll_interrupt() {
+                    self.hardware_interrupt(interrupt);
+                }
             }
             callback(self);
+            if self.halted {
+                return Ok(());
+            }
+            if self.jammed {
+                // only a reset brings the cpu back, the rest of the system keeps running.
+                // the callback sees the jam once, then once a frame to stop or reset
+                self.idle_frame();
+                continue;
+            }
+            let irq_inhibit = self.status.contains(StatusFlags::INTERRUPT_DISABLE);
+            self.bus.fetch_instruction(self.program_counter);
             let code = self.mem_read(self.program_counter);
 
             // println!(
             //     "code = {:x}, program_counter = {:x}",
             //     code, self.program_counter
             // );
-            self.program_counter += 1;
+            self.program_counter = self.program_counter.wrapping_add(1);
             let program_counter_state = self.program_counter;
-            let opcode = OPCODES_MAP.get(&code).expect("opcode not found");
+            let opcode = OPCODES[code as usize];
             // println!("opcode = {:?}", opcode);
-
-            match code {
-                // LDA
-                0xA9 | 0xa5 | 0xB5 | 0xAD | 0xBD | 0xB9 | 0xA1 | 0xB1 => {
-                    self.lda(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                // ASL with Accumulator
-                0x0a => {
-                    self.asl_accumulator();
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                // ASL with memory
-                0x06 | 0x16 | 0x0e | 0x1e => {
-                    let value = self.asl(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_a);
-                    self.update_zero_and_negative_flags(value);
-                }
-                // LSR with Accumulator
-                0x4a => {
-                    self.lsr_accumulator();
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                0x46 | 0x56 | 0x4e | 0x5e => {
-                    let value = self.lsr(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_a);
-                    self.update_zero_and_negative_flags(value);
-                }
-                // ROR with Accumulator
-                0x6a => {
-                    self.ror_accumulator();
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                0x66 | 0x76 | 0x6e | 0x7e => {
-                    let value = self.ror(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_a);
-                    self.update_zero_and_negative_flags(value);
-                }
-                // ROL with Accumulator
-                0x2a => {
-                    self.rol_accumulator();
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                0x26 | 0x36 | 0x2e | 0x3e => {
-                    let value = self.rol(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_a);
-                    self.update_zero_and_negative_flags(value);
-                }
-                // LDX
-                0xA2 | 0xA6 | 0xB6 | 0xAE | 0xBE => {
-                    self.ldx(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_x);
-                }
-                // LDY
-                0xA0 | 0xA4 | 0xB4 | 0xAC | 0xBC => {
-                    self.ldy(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_y);
-                }
-                // TAX
-                0xAA => {
-                    self.tax();
-                    self.update_zero_and_negative_flags(self.register_x);
-                }
-                0x8A => {
-                    self.txa();
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                // TAY
-                0xA8 => {
-                    self.tay();
-                    self.update_zero_and_negative_flags(self.register_y);
-                }
-                // TYA
-                0x98 => {
-                    self.tya();
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                // INC
-                0xe6 | 0xf6 | 0xee | 0xfe => {
-                    self.inc(&opcode.mode);
-                }
-                0xe8 => {
-                    self.inx();
-                    self.update_zero_and_negative_flags(self.register_x);
-                }
-                0xc8 => {
-                    self.iny();
-                    self.update_zero_and_negative_flags(self.register_y);
-                }
-                // DEC
-                0xc6 | 0xd6 | 0xce | 0xde => {
-                    self.dec(&opcode.mode);
-                }
-                // DEX
-                0xca => {
-                    self.dex();
-                    self.update_zero_and_negative_flags(self.register_x);
-                }
-                // DEY
-                0x88 => {
-                    self.dey();
-                    self.update_zero_and_negative_flags(self.register_y);
-                }
-                // BCS
-                0xb0 => {
-                    self.branch(self.status.contains(StatusFlags::CARRY), &opcode.mode);
-                }
-                // BCC
-                0x90 => {
-                    self.branch(!self.status.contains(StatusFlags::CARRY), &opcode.mode);
-                }
-                // BEQ
-                0xf0 => {
-                    self.branch(self.status.contains(StatusFlags::ZERO), &opcode.mode);
-                }
-                // BNE
-                0xd0 => {
-                    self.branch(!self.status.contains(StatusFlags::ZERO), &opcode.mode);
-                }
-                // BVS
-                0x70 => {
-                    self.branch(self.status.contains(StatusFlags::OVERFLOW), &opcode.mode);
-                }
-                // BVC
-                0x50 => {
-                    self.branch(!self.status.contains(StatusFlags::OVERFLOW), &opcode.mode);
-                }
-                // BPL
-                0x10 => {
-                    self.branch(!self.status.contains(StatusFlags::NEGATIVE), &opcode.mode);
-                }
-                // BMI
-                0x30 => {
-                    self.branch(self.status.contains(StatusFlags::NEGATIVE), &opcode.mode);
-                }
-                // TSX
-                0xba => {
-                    self.tsx();
-                    self.update_zero_and_negative_flags(self.register_x);
-                }
-                // PHA
-                0x48 => {
-                    self.pha();
-                }
-                // PLA
-                0x68 => {
-                    self.pla();
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                // PHP
-                0x08 => {
-                    self.php();
-                }
-                // PLP
-                0x28 => {
-                    self.plp();
-                }
-                // TXS
-                0x9a => {
-                    self.txs();
-                }
-                // JMP
-                0x4c => {
-                    self.jmp();
-                }
-                0x6c => {
-                    self.jmp_indirect();
-                }
-                // JSR
-                0x20 => {
-                    self.jsr();
-                }
-                // RTS
-                0x60 => {
-                    self.rts();
-                }
-                // RTI
-                0x40 => {
-                    self.rti();
-                }
-                // CMP
-                0xc9 | 0xc5 | 0xd5 | 0xcd | 0xdd | 0xd9 | 0xc1 | 0xd1 => {
-                    self.cmp(&opcode.mode, self.register_a);
-                }
-                // CPX
-                0xe0 | 0xe4 | 0xec => {
-                    self.cmp(&opcode.mode, self.register_x);
-                }
-                // CPY
-                0xc0 | 0xc4 | 0xcc => {
-                    self.cmp(&opcode.mode, self.register_y);
-                }
-                /* STA */
-                0x85 | 0x95 | 0x8D | 0x9D | 0x99 | 0x81 | 0x91 => {
-                    self.sta(&opcode.mode);
-                }
-                /* STX */
-                0x86 | 0x96 | 0x8e => {
-                    self.stx(&opcode.mode);
-                }
-                /* STY */
-                0x84 | 0x94 | 0x8c => {
-                    self.sty(&opcode.mode);
-                }
-                /* ADC */
-                0x69 | 0x65 | 0x75 | 0x6D | 0x7D | 0x79 | 0x61 | 0x71 => {
-                    self.adc(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                /* SBC */
-                0xe9 | 0xe5 | 0xf5 | 0xed | 0xfd | 0xf9 | 0xe1 | 0xf1 => {
-                    self.sbc(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                /* AND */
-                0x29 | 0x25 | 0x35 | 0x2d | 0x3d | 0x39 | 0x21 | 0x31 => {
-                    self.and(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                /* EOR */
-                0x49 | 0x45 | 0x55 | 0x4d | 0x5d | 0x59 | 0x41 | 0x51 => {
-                    self.eor(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                /* ORA */
-                0x09 | 0x05 | 0x15 | 0x0d | 0x1d | 0x19 | 0x01 | 0x11 => {
-                    self.ora(&opcode.mode);
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                /* BIT */
-                0x24 | 0x2c => {
-                    self.bit(&opcode.mode);
-                }
-                /* SEC */
-                0x38 => {
-                    self.sec();
-                }
-                /* CLC */
-                0x18 => {
-                    self.clc();
-                }
-                /* SED */
-                0xf8 => {
-                    self.sed();
-                }
-                /* CLD */
-                0xd8 => {
-                    self.cld();
-                }
-                /* SEI */
-                0x78 => {
-                    self.sei();
-                }
-                /* CLI */
-                0x58 => {
-                    self.cli();
-                }
-                /* CLV */
-                0xb8 => {
-                    self.clv();
-                }
-                /* NOP */
-                0xea => {
-                    self.nop();
-                }
-                /* BRK */
-                0x00 => {
-                    self.brk();
-                    break;
-                }
-                // UnOfficial
-                // *DCP
-                0xc7 | 0xd7 | 0xcf | 0xdf | 0xdb | 0xd3 | 0xc3 | 0xd3 => {
-                    let (addr, _) = self.get_operand_address(&opcode.mode);
-                    let mut data = self.mem_read(addr);
-                    data = data.wrapping_sub(1);
-                    self.mem_write(addr, data);
-                    // self._update_zero_and_negative_flags(data);
-                    if data <= self.register_a {
-                        self.status.insert(StatusFlags::CARRY);
-                    }
-
-                    self.update_zero_and_negative_flags(self.register_a.wrapping_sub(data));
-                }
-                // *RLA
-                0x27 | 0x37 | 0x2f | 0x3f | 0x3b | 0x33 | 0x23 | 0x33 => {
-                    let data = self.rol(&opcode.mode);
-                    self.register_a &= data;
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                // *SLO
-                0x07 | 0x17 | 0x0f | 0x1f | 0x1b | 0x03 | 0x13 => {
-                    let data = self.asl(&opcode.mode);
-                    self.register_a |= data;
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                // *SRE
-                0x47 | 0x57 | 0x4f | 0x5f | 0x5b | 0x43 | 0x53 => {
-                    let data = self.lsr(&opcode.mode);
-                    self.register_a ^= data;
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-                // *SKB
-                0x80 | 0x82 | 0x89 | 0xc2 | 0xe2 => {}
-                // *AXS
-                0xcb => {
-                    let (addr, _) = self.get_operand_address(&opcode.mode);
-                    let data = self.mem_read(addr);
-                    let result = (self.register_a & self.register_x).wrapping_sub(data);
-                    if self.register_a >= data {
-                        self.status = self.status | StatusFlags::CARRY;
-                    }
-                    self.update_zero_and_negative_flags(result);
-                    self.register_x = result;
-                }
-                // *ARR
-                0x6b => {
-                    let (addr, _) = self.get_operand_address(&opcode.mode);
-                    let data = self.mem_read(addr);
-                    self.register_a &= data;
-                    self.update_zero_and_negative_flags(self.register_a);
-                    self.ror_accumulator();
-
-                    let result = self.register_a;
-                    let bit_5 = (result >> 5) & 1;
-                    let bit_6 = (result >> 6) & 1;
-                    if bit_6 == 1 {
-                        self.status = self.status | StatusFlags::CARRY;
-                    } else {
-                        self.status = self.status & !StatusFlags::CARRY;
-                    }
-
-                    if bit_5 ^ bit_6 == 1 {
-                        self.status = self.status | StatusFlags::OVERFLOW;
-                    } else {
-                        self.status = self.status & !StatusFlags::OVERFLOW;
-                    }
-
-                    self.update_zero_and_negative_flags(result);
-                }
-                /* unofficial SBC */
-                0xeb => {
-                    let (addr, _) = self.get_operand_address(&opcode.mode);
-                    let data = self.mem_read(addr);
-                    self.sub_from_register_a(data);
-                }
-
-                /* ANC */
-                0x0b | 0x2b => {
-                    let (addr, _) = self.get_operand_address(&opcode.mode);
-                    let data = self.mem_read(addr);
-                    self.register_a &= data;
-                    self.update_zero_and_negative_flags(self.register_a);
-                    if self.status.contains(StatusFlags::NEGATIVE) {
-                        self.status.insert(StatusFlags::CARRY);
-                    } else {
-                        self.status.remove(StatusFlags::CARRY);
-                    }
-                }
-
-                /* ALR */
-                0x4b => {
-                    let (addr, _) = self.get_operand_address(&opcode.mode);
-                    let data = self.mem_read(addr);
-                    self.register_a &= data;
-                    self.update_zero_and_negative_flags(self.register_a);
-                    self.lsr_accumulator();
-                }
-
-                // *NOP
-                0x04 | 0x44 | 0x64 | 0x14 | 0x34 | 0x54 | 0x74 | 0xd4 | 0xf4 | 0x0c | 0x1c
-                | 0x3c | 0x5c | 0x7c | 0xdc | 0xfc => {
-                    let (addr, page_cross) = self.get_operand_address(&opcode.mode);
-                    let data = self.mem_read(addr);
-                    if page_cross {
-                        self.bus.tick(1);
-                    }
-                }
-
-                /* RRA */
-                0x67 | 0x77 | 0x6f | 0x7f | 0x7b | 0x63 | 0x73 => {
-                    let data = self.ror(&opcode.mode);
-                    self.add_to_register_a(data);
-                }
-
-                /* ISB */
-                0xe7 | 0xf7 | 0xef | 0xff | 0xfb | 0xe3 | 0xf3 => {
-                    let data = self.inc(&opcode.mode);
-                    self.sub_from_register_a(data);
-                }
-
-                /* NOPs */
-                0x02 | 0x12 | 0x22 | 0x32 | 0x42 | 0x52 | 0x62 | 0x72 | 0x92 | 0xb2 | 0xd2
-                | 0xf2 => { /* do nothing */ }
-
-                0x1a | 0x3a | 0x5a | 0x7a | 0xda | 0xfa => { /* do nothing */ }
-
-                /* LAX */
-                0xa7 | 0xb7 | 0xaf | 0xbf | 0xa3 | 0xb3 => {
-                    let (addr, _) = self.get_operand_address(&opcode.mode);
-                    let data = self.mem_read(addr);
-                    self.register_a = data;
-                    self.update_zero_and_negative_flags(self.register_a);
-                    self.register_x = self.register_a;
-                }
-
-                /* SAX */
-                0x87 | 0x97 | 0x8f | 0x83 => {
-                    let data = self.register_a & self.register_x;
-                    let (addr, _) = self.get_operand_address(&opcode.mode);
-                    self.mem_write(addr, data);
-                }
-
-                /* LXA */
-                0xab => {
-                    self.lda(&opcode.mode);
-                    self.tax();
-                }
-
-                /* XAA */
-                0x8b => {
-                    self.register_a = self.register_x;
-                    self.update_zero_and_negative_flags(self.register_a);
-                    let (addr, _) = self.get_operand_address(&opcode.mode);
-                    let data = self.mem_read(addr);
-                    self.register_a &= data;
-                    self.update_zero_and_negative_flags(self.register_a);
-                }
-
-                /* LAS */
-                0xbb => {
-                    let (addr, _) = self.get_operand_address(&opcode.mode);
-                    let mut data = self.mem_read(addr);
-                    data = data & self.stack_pointer;
-                    self.register_a = data;
-                    self.register_x = data;
-                    self.stack_pointer = data;
-                    self.update_zero_and_negative_flags(data);
-                }
-
-                /* TAS */
-                0x9b => {
-                    let data = self.register_a & self.register_x;
-                    self.stack_pointer = data;
-                    let mem_address =
-                        self.mem_read_u16(self.program_counter) + self.register_y as u16;
-
-                    let data = ((mem_address >> 8) as u8 + 1) & self.stack_pointer;
-                    self.mem_write(mem_address, data)
-                }
-
-                /* AHX  Indirect Y */
-                0x93 => {
-                    let pos: u8 = self.mem_read(self.program_counter);
-                    let mem_address = self.mem_read_u16(pos as u16) + self.register_y as u16;
-                    let data = self.register_a & self.register_x & (mem_address >> 8) as u8;
-                    self.mem_write(mem_address, data)
-                }
-
-                /* AHX Absolute Y*/
-                0x9f => {
-                    let mem_address =
-                        self.mem_read_u16(self.program_counter) + self.register_y as u16;
-
-                    let data = self.register_a & self.register_x & (mem_address >> 8) as u8;
-                    self.mem_write(mem_address, data)
-                }
-
-                /* SHX */
-                0x9e => {
-                    let mem_address =
-                        self.mem_read_u16(self.program_counter) + self.register_y as u16;
-
-                    // todo if cross page boundry {
-                    //     mem_address &= (self.x as u16) << 8;
-                    // }
-                    let data = self.register_x & ((mem_address >> 8) as u8 + 1);
-                    self.mem_write(mem_address, data)
-                }
-
-                /* SHY */
-                0x9c => {
-                    let mem_address =
-                        self.mem_read_u16(self.program_counter) + self.register_x as u16;
-                    let data = self.register_y & ((mem_address >> 8) as u8 + 1);
-                    self.mem_write(mem_address, data)
-                }
-
-                _ => todo!(),
+            if opcode.bytes_len == 1 {
+                // implied and accumulator instructions still read the next byte
+                self.dummy_read(self.program_counter);
             }
 
-            self.bus.tick(opcode.cycles);
+            (Self::DISPATCH[code as usize])(self, opcode);
 
             if program_counter_state == self.program_counter {
-                self.program_counter += (opcode.bytes_len - 1) as u16;
+                self.program_counter = self
+                    .program_counter
+                    .wrapping_add((opcode.bytes_len - 1) as u16);
             }
+            if self.jammed && self.jam_policy == JamPolicy::Error {
+                return Err(format!(
+                    "cpu jammed by opcode {:02x} at {:04x}",
+                    code, self.program_counter
+                ));
+            }
+            // CLI, SEI and PLP change I after the interrupt poll,
+            // so the old value still applies before the next instruction.
+            self.irq_inhibit = match code {
+                0x58 | 0x78 | 0x28 => irq_inhibit,
+                _ => self.status.contains(StatusFlags::INTERRUPT_DISABLE),
+            };
         }
     }
-    pub fn run(&mut self) {
-        self.run_with_callback(|_| {});
+    pub fn run(&mut self) -> Result<(), String> {
+        self.run_with_callback(|_| {})
     }
 }
-impl<'a> Stack for CPU<'a> {
+impl<M: CpuBus> Stack for CPU<M> {
     fn stack_push(&mut self, data: u8) {
         self.mem_write((STACK_BASE as u16) + self.stack_pointer as u16, data);
         self.stack_pointer = self.stack_pointer.wrapping_sub(1)
@@ -673,15 +316,51 @@ impl<'a> Stack for CPU<'a> {
     }
 }
 
-impl<'a> CPU<'a> {
+impl CPU<FlatBus> {
+    pub fn load_and_run(&mut self, program: Vec<u8>) -> Result<(), String> {
+        // launch or inserting new cartridge, then reset program ROM address state.
+        self.load(program);
+        self.reset();
+        self.run_until_brk()
+    }
+    // run until the next BRK, which is left unexecuted.
+    pub fn run_until_brk(&mut self) -> Result<(), String> {
+        self.run_with_callback(|cpu| {
+            if cpu.bus.mem_read(cpu.program_counter) == 0x00 {
+                cpu.halted = true;
+            }
+        })
+    }
+    // copy the program to $8000 and point the reset vector at it.
+    pub fn load(&mut self, program: Vec<u8>) {
+        for (i, data) in program.iter().enumerate() {
+            self.bus.mem_write(0x8000 + i as u16, *data);
+        }
+        // 0xFFFC: program counter address set start point.
+        self.bus.mem_write_u16(0xFFFC, 0x8000);
+    }
+}
+
+impl<M: CpuBus> CPU<M> {
     pub fn reset(&mut self) {
         self.register_a = 0;
         self.register_x = 0;
         self.register_y = 0;
         self.status = StatusFlags::from_bits_truncate(0b0010_0100);
 
-        self.stack_pointer = 0xFD;
-        self.program_counter = self.mem_read_u16(0xFFFC);
+        // reset runs the interrupt sequence (7 cycles) with the stack writes
+        // turned into reads, which is why the stack pointer ends up at $FD.
+        self.dummy_read(self.program_counter);
+        self.dummy_read(self.program_counter);
+        self.stack_pointer = 0;
+        for _ in 0..3 {
+            self.dummy_read(STACK_BASE + self.stack_pointer as u16);
+            self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        }
+        self.program_counter = self.mem_read_u16(RESET_VECTOR);
+        self.irq_inhibit = true;
+        self.halted = false;
+        self.jammed = false;
     }
     fn asl_accumulator(&mut self) {
         let value = self.register_a;
@@ -695,8 +374,7 @@ impl<'a> CPU<'a> {
     }
 
     fn asl(&mut self, mode: &AddressingMode) -> u8 {
-        let (addr, _) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
+        let (addr, value) = self.read_modify_write_operand(mode);
         self.status = if value >> 7 == 1 {
             self.status | StatusFlags::CARRY
         } else {
@@ -718,8 +396,7 @@ impl<'a> CPU<'a> {
     }
 
     fn lsr(&mut self, mode: &AddressingMode) -> u8 {
-        let (addr, _) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
+        let (addr, value) = self.read_modify_write_operand(mode);
         self.status = if value & StatusFlags::CARRY.bits() == 1 {
             self.status | StatusFlags::CARRY
         } else {
@@ -742,8 +419,7 @@ impl<'a> CPU<'a> {
         self.register_a = result;
     }
     fn ror(&mut self, mode: &AddressingMode) -> u8 {
-        let (addr, _) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
+        let (addr, value) = self.read_modify_write_operand(mode);
         let current_carry = self.status.bits() & StatusFlags::CARRY.bits();
         let old_carry = value & StatusFlags::CARRY.bits();
         self.status = if old_carry > 0 {
@@ -768,8 +444,7 @@ impl<'a> CPU<'a> {
         self.register_a = result;
     }
     fn rol(&mut self, mode: &AddressingMode) -> u8 {
-        let (addr, _) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
+        let (addr, value) = self.read_modify_write_operand(mode);
         let current_carry = self.status.bits() & StatusFlags::CARRY.bits();
         let old_carry = value >> 7;
         self.status = if old_carry > 0 {
@@ -783,30 +458,18 @@ impl<'a> CPU<'a> {
     }
 
     fn lda(&mut self, mode: &AddressingMode) {
-        let (addr, page_cross) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
+        let value = self.read_operand(mode);
 
         self.register_a = value;
-        if page_cross {
-            self.bus.tick(1);
-        }
     }
 
     fn ldx(&mut self, mode: &AddressingMode) {
-        let (addr, page_cross) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
+        let value = self.read_operand(mode);
         self.register_x = value;
-        if page_cross {
-            self.bus.tick(1);
-        }
     }
     fn ldy(&mut self, mode: &AddressingMode) {
-        let (addr, page_cross) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
+        let value = self.read_operand(mode);
         self.register_y = value;
-        if page_cross {
-            self.bus.tick(1);
-        }
     }
 
     fn tax(&mut self) {
@@ -840,8 +503,7 @@ impl<'a> CPU<'a> {
     }
 
     fn inc(&mut self, mode: &AddressingMode) -> u8 {
-        let (addr, _) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
+        let (addr, value) = self.read_modify_write_operand(mode);
         let data = value.wrapping_add(1);
         self.mem_write(addr, data);
         self.update_zero_and_negative_flags(data);
@@ -854,8 +516,7 @@ impl<'a> CPU<'a> {
         self.register_y = self.register_y.wrapping_add(1);
     }
     fn dec(&mut self, mode: &AddressingMode) {
-        let (addr, _) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
+        let (addr, value) = self.read_modify_write_operand(mode);
         let data = value.wrapping_sub(1);
         self.mem_write(addr, data);
         self.update_zero_and_negative_flags(data)
@@ -874,8 +535,7 @@ impl<'a> CPU<'a> {
     }
 
     fn cmp(&mut self, mode: &AddressingMode, compare_with: u8) {
-        let (addr, page_cross) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
+        let value = self.read_operand(mode);
         let result = compare_with.wrapping_sub(value);
         self.status = if value <= compare_with {
             self.status | StatusFlags::CARRY
@@ -883,104 +543,51 @@ impl<'a> CPU<'a> {
             self.status & !StatusFlags::CARRY
         };
         self.update_zero_and_negative_flags(result);
-        if page_cross {
-            self.bus.tick(1);
-        }
     }
 
-    fn cpx(&mut self, mode: &AddressingMode) {
-        let (addr, page_cross) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
-        let result = self.register_x.wrapping_sub(value);
-        self.status = if self.register_x >= value {
-            self.status | StatusFlags::CARRY
-        } else {
-            self.status & !StatusFlags::CARRY
-        };
-        self.status = if self.register_x == value {
-            self.status | StatusFlags::ZERO
-        } else {
-            self.status & !StatusFlags::ZERO
-        };
-        self.status = if result & StatusFlags::NEGATIVE.bits() > 0 {
-            self.status | StatusFlags::NEGATIVE
-        } else {
-            self.status & !StatusFlags::NEGATIVE
-        };
-        if page_cross {
-            self.bus.tick(1);
-        }
-    }
-    fn cpy(&mut self, mode: &AddressingMode) {
-        let (addr, page_cross) = self.get_operand_address(mode);
-        let value = self.mem_read(addr);
-        let result = self.register_y.wrapping_sub(value);
-        self.status = if self.register_y >= value {
-            self.status | StatusFlags::CARRY
-        } else {
-            self.status & !StatusFlags::CARRY
-        };
-        self.status = if self.register_y == value {
-            self.status | StatusFlags::ZERO
-        } else {
-            self.status & !StatusFlags::ZERO
-        };
-        self.status = if result & StatusFlags::NEGATIVE.bits() > 0 {
-            self.status | StatusFlags::NEGATIVE
-        } else {
-            self.status & !StatusFlags::NEGATIVE
-        };
-        if page_cross {
-            self.bus.tick(1);
-        }
-    }
     fn branch(&mut self, condition: bool, mode: &AddressingMode) {
+        // the offset is fetched whether the branch is taken or not
+        let offset = self.mem_read(self.program_counter) as i8;
         if condition {
-            let offset = self.mem_read(self.program_counter) as i16;
-            let mut tmp = self.program_counter.wrapping_add(1);
-            let jump_addr: u16;
-            if offset >= 128 {
-                jump_addr = tmp.wrapping_add((offset - 256) as u16);
-            } else {
-                jump_addr = tmp.wrapping_add(offset as u16);
+            let tmp = self.program_counter.wrapping_add(1);
+            let jump_addr = tmp.wrapping_add(offset as u16);
+            // taken: one more cycle, and one more to fix the high byte on a page cross
+            self.dummy_read(tmp);
+            if self.page_cross(tmp, jump_addr) {
+                self.dummy_read((tmp & 0xFF00) | (jump_addr & 0x00FF));
             }
-            self.program_counter = jump_addr & 0xFFFF;
-            // let jump_addr = self
-            //     .program_counter
-            //     .wrapping_add(1)
-            //     .wrapping_add(offset as u16);
-            // self.program_counter = jump_addr;
-            let t = if ((tmp ^ self.program_counter) & 0x100) > 0 {
-                2
-            } else {
-                1
-            };
-            self.bus.tick(t);
+            self.program_counter = jump_addr;
         }
     }
 
     fn sta(&mut self, mode: &AddressingMode) {
-        let (addr, _) = self.get_operand_address(mode);
+        let addr = self.write_operand_address(mode);
         self.mem_write(addr, self.register_a);
     }
     fn stx(&mut self, mode: &AddressingMode) {
-        let (addr, _) = self.get_operand_address(mode);
+        let addr = self.write_operand_address(mode);
         self.mem_write(addr, self.register_x);
     }
     fn sty(&mut self, mode: &AddressingMode) {
-        let (addr, _) = self.get_operand_address(mode);
+        let addr = self.write_operand_address(mode);
         self.mem_write(addr, self.register_y);
     }
     fn jsr(&mut self) {
+        // the high byte of the target is read after the return address is pushed
+        let lo = self.mem_read(self.program_counter) as u16;
+        self.dummy_read(STACK_BASE + self.stack_pointer as u16);
         self.stack_push_u16(self.program_counter + 2 - 1);
-        let target_address = self.mem_read_u16(self.program_counter);
-        self.program_counter = target_address
+        let hi = self.mem_read(self.program_counter + 1) as u16;
+        self.program_counter = hi << 8 | lo
     }
     fn rts(&mut self) {
+        self.dummy_read(STACK_BASE + self.stack_pointer as u16);
         let target_address = self.stack_pop_u16();
+        self.dummy_read(target_address);
         self.program_counter = target_address + 1;
     }
     fn rti(&mut self) {
+        self.dummy_read(STACK_BASE + self.stack_pointer as u16);
         self.status = StatusFlags::from_bits_truncate(self.stack_pop());
         self.status = self.status & !StatusFlags::BREAK;
         self.status = self.status | StatusFlags::RESERVED;
@@ -991,6 +598,7 @@ impl<'a> CPU<'a> {
         self.stack_push(a);
     }
     fn pla(&mut self) {
+        self.dummy_read(STACK_BASE + self.stack_pointer as u16);
         self.register_a = self.stack_pop();
     }
     fn php(&mut self) {
@@ -1000,6 +608,7 @@ impl<'a> CPU<'a> {
         self.stack_push(value);
     }
     fn plp(&mut self) {
+        self.dummy_read(STACK_BASE + self.stack_pointer as u16);
         self.status = StatusFlags::from_bits_truncate(self.stack_pop());
         self.status = self.status & !StatusFlags::BREAK;
         self.status = self.status | StatusFlags::RESERVED;
@@ -1037,8 +646,7 @@ impl<'a> CPU<'a> {
         self.add_to_register_a(((data as i8).wrapping_neg().wrapping_sub(1)) as u8);
     }
     fn adc(&mut self, mode: &AddressingMode) {
-        let (addr, page_cross) = self.get_operand_address(mode);
-        let value = self.mem_read(addr); // M
+        let value = self.read_operand(mode); // M
         let a = self.register_a as u16; // A
         let sum = a + value as u16;
         let has_carry = self.status.contains(StatusFlags::CARRY);
@@ -1062,18 +670,14 @@ impl<'a> CPU<'a> {
             self.status & !StatusFlags::OVERFLOW
         };
         self.register_a = sum_with_carry as u8;
-        if page_cross {
-            self.bus.tick(1);
-        }
     }
 
     fn sbc(&mut self, mode: &AddressingMode) {
-        let (addr, page_cross) = self.get_operand_address(mode);
-        let value = self.mem_read(addr); // M
-                                         // A - M - (1 - C)
-                                         // = A - M - 1 + C
-                                         // = A - (M + 1) + C
-                                         // = A + (-M + -1) + C
+        let value = self.read_operand(mode); // M
+                                             // A - M - (1 - C)
+                                             // = A - M - 1 + C
+                                             // = A - (M + 1) + C
+                                             // = A + (-M + -1) + C
         let a = self.register_a as u16; // A
         let b = (value as i8).wrapping_neg().wrapping_sub(1) as u8;
         let sum_with_carry = if self.status.contains(StatusFlags::CARRY) {
@@ -1102,37 +706,21 @@ impl<'a> CPU<'a> {
             self.status & !StatusFlags::OVERFLOW
         };
         self.register_a = sum_with_carry as u8;
-        if page_cross {
-            self.bus.tick(1);
-        }
     }
     fn and(&mut self, mode: &AddressingMode) {
-        let (addr, page_cross) = self.get_operand_address(mode);
-        let value = self.mem_read(addr); // M
+        let value = self.read_operand(mode); // M
         self.register_a = self.register_a & value;
-        if page_cross {
-            self.bus.tick(1);
-        }
     }
     fn eor(&mut self, mode: &AddressingMode) {
-        let (addr, page_cross) = self.get_operand_address(mode);
-        let value = self.mem_read(addr); // M
+        let value = self.read_operand(mode); // M
         self.register_a = self.register_a ^ value;
-        if page_cross {
-            self.bus.tick(1);
-        }
     }
     fn ora(&mut self, mode: &AddressingMode) {
-        let (addr, page_cross) = self.get_operand_address(mode);
-        let value = self.mem_read(addr); // M
+        let value = self.read_operand(mode); // M
         self.register_a = self.register_a | value;
-        if page_cross {
-            self.bus.tick(1);
-        }
     }
     fn bit(&mut self, mode: &AddressingMode) {
-        let (addr, _) = self.get_operand_address(mode);
-        let value = self.mem_read(addr); // M
+        let value = self.read_operand(mode); // M
         let result = self.register_a & value;
         self.status = if result == 0 {
             self.status | StatusFlags::ZERO
@@ -1151,9 +739,6 @@ impl<'a> CPU<'a> {
         };
     }
 
-    fn brk(&mut self) {
-        self.status = self.status | StatusFlags::BREAK;
-    }
     fn sec(&mut self) {
         self.status = self.status | StatusFlags::CARRY;
     }
@@ -1175,7 +760,6 @@ impl<'a> CPU<'a> {
     fn clv(&mut self) {
         self.status = self.status & !StatusFlags::OVERFLOW;
     }
-    fn nop(&self) {}
 
     fn update_zero_and_negative_flags(&mut self, value: u8) {
         self.status = if value == 0 {
@@ -1195,23 +779,168 @@ impl<'a> CPU<'a> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::bus::Bus;
+    use crate::cartridge::rom::test::test_rom;
 
-    pub fn new_test<'a>() -> CPU<'a> {
-        CPU {
-            register_a: 0,
-            register_x: 0,
-            register_y: 0,
-            status: StatusFlags::from_bits_truncate(0b100100),
-            program_counter: 0,
-            stack_pointer: 0,
-            bus: Bus::new(crate::cartridge::rom::test::test_rom(), |_| {}),
-            memory: [0; 0xffff],
+    pub fn new_test() -> CPU<FlatBus> {
+        CPU::new(FlatBus::new())
+    }
+    // cycles between the first and the second instruction fetch
+    fn first_instruction_cycles(cpu: &mut CPU<FlatBus>) -> usize {
+        let mut starts = vec![];
+        cpu.run_with_callback(|cpu| {
+            starts.push(cpu.bus.cycles);
+            cpu.halted = starts.len() == 2;
+        })
+        .unwrap();
+        starts[1] - starts[0]
+    }
+
+    #[test]
+    fn test_all_opcodes_are_in_the_table() {
+        let mut codes = CPU_OPS_CODES.iter().map(|op| op.code).collect::<Vec<u8>>();
+        codes.sort();
+        assert_eq!(codes, (0..=255).collect::<Vec<u8>>());
+        for code in 0..=255u8 {
+            assert_eq!(OPCODES[code as usize].code, code);
+        }
+    }
+
+    #[test]
+    fn test_opcode_cycles() {
+        for op in CPU_OPS_CODES.iter().filter(|op| op.name != "*JAM") {
+            let mut cpu = new_test();
+            cpu.load(vec![op.code, 0x10, 0x02]);
+            cpu.reset();
+            // P = 0x24: BCC, BNE, BVC and BPL are taken
+            let branch_taken = matches!(op.code, 0x90 | 0xd0 | 0x50 | 0x10);
+            let expected = op.cycles as usize + if branch_taken { 1 } else { 0 };
+            assert_eq!(
+                first_instruction_cycles(&mut cpu),
+                expected,
+                "{} {:02x}",
+                op.name,
+                op.code
+            );
         }
     }
+
+    #[test]
+    fn test_jam_policy() {
+        let mut cpu = new_test();
+        cpu.load(vec![0xe8, 0x02, 0xe8]);
+        cpu.reset();
+        cpu.jam_policy = JamPolicy::Error;
+        assert!(cpu.run().is_err());
+        assert_eq!(cpu.program_counter, 0x8001);
+        assert_eq!(cpu.register_x, 1);
+
+        // the cpu stays on the JAM opcode while the bus keeps running
+        cpu.reset();
+        cpu.jam_policy = JamPolicy::Halt;
+        let mut count = 0;
+        cpu.run_with_callback(|cpu| {
+            count += 1;
+            cpu.halted = count == 10;
+        })
+        .unwrap();
+        assert!(cpu.jammed);
+        assert_eq!(cpu.program_counter, 0x8001);
+        assert_eq!(cpu.register_x, 1);
+
+        cpu.reset();
+        cpu.jam_policy = JamPolicy::Reset;
+        let mut starts = vec![];
+        cpu.run_with_callback(|cpu| {
+            starts.push(cpu.program_counter);
+            cpu.halted = starts.len() == 3;
+        })
+        .unwrap();
+        assert_eq!(starts, vec![0x8000, 0x8001, 0x8000]);
+        assert_eq!(cpu.register_x, 0);
+    }
+
+    #[test]
+    fn test_jammed_callback() {
+        let mut bus = Bus::new(test_rom(), |_, _, _| {});
+        // the reset vector of the test rom points at $0101
+        bus.mem_write(0x0101, 0x02);
+        let mut cpu = CPU::new(bus);
+        cpu.reset();
+        let mut calls = 0;
+        cpu.run_with_callback(|cpu| {
+            calls += 1;
+            cpu.halted = cpu.bus.cycles() > 3 * 29781;
+        })
+        .unwrap();
+        assert!(cpu.jammed);
+        // the jam, then about once a frame
+        assert!(calls <= 6, "{}", calls);
+    }
+
+    #[test]
+    fn test_axs() {
+        let mut cpu = new_test();
+        // SEC, LDA #$f3, LDX #$3c, AXS #$31: A&X is $30
+        cpu.load_and_run(vec![0x38, 0xa9, 0xf3, 0xa2, 0x3c, 0xcb, 0x31, 0x00])
+            .unwrap();
+        assert_eq!(cpu.register_x, 0xff);
+        assert!(!cpu.status.contains(StatusFlags::CARRY));
+        assert!(cpu.status.contains(StatusFlags::NEGATIVE));
+
+        // LDA #$f3, LDX #$3c, AXS #$30
+        cpu.load_and_run(vec![0xa9, 0xf3, 0xa2, 0x3c, 0xcb, 0x30, 0x00])
+            .unwrap();
+        assert_eq!(cpu.register_x, 0);
+        assert!(cpu.status.contains(StatusFlags::CARRY | StatusFlags::ZERO));
+    }
+
+    #[test]
+    fn test_xaa() {
+        let mut cpu = new_test();
+        // LDA #$01, LDX #$ff, XAA #$ff
+        cpu.load_and_run(vec![0xa9, 0x01, 0xa2, 0xff, 0x8b, 0xff, 0x00])
+            .unwrap();
+        assert_eq!(cpu.register_a, 0xef);
+    }
+
+    #[test]
+    fn test_shx_page_cross() {
+        let mut cpu = new_test();
+        // LDX #$03, LDY #$20, SHX $01f0,Y
+        cpu.load_and_run(vec![0xa2, 0x03, 0xa0, 0x20, 0x9e, 0xf0, 0x01, 0x00])
+            .unwrap();
+        // X & ($01 + 1) = $02 lands at $0210
+        assert_eq!(cpu.bus.mem_read(0x0210), 0x02);
+    }
+
+    #[test]
+    fn test_page_cross_cycles() {
+        // LDA $02f0,X reads $0210 after a dummy read of $0110
+        let mut cpu = new_test();
+        cpu.load(vec![0xbd, 0xf0, 0x02]);
+        cpu.reset();
+        cpu.register_x = 0x20;
+        assert_eq!(first_instruction_cycles(&mut cpu), 5);
+
+        // STA $02f0,X always takes 5 cycles
+        let mut cpu = new_test();
+        cpu.load(vec![0x9d, 0x00, 0x02]);
+        cpu.reset();
+        assert_eq!(first_instruction_cycles(&mut cpu), 5);
+
+        // a taken branch to the next page takes 4 cycles
+        let mut cpu = new_test();
+        cpu.bus.mem_write(0x80f0, 0xd0);
+        cpu.bus.mem_write(0x80f1, 0x20);
+        cpu.program_counter = 0x80f0;
+        assert_eq!(first_instruction_cycles(&mut cpu), 4);
+    }
+
     #[test]
     fn test_0x9a_lda_immediate_load_data() {
         let mut cpu = new_test();
-        cpu.load_and_run(vec![0xA9, 0x05, 0x00]);
+        cpu.load_and_run(vec![0xA9, 0x05, 0x00]).unwrap();
         assert_eq!(cpu.register_a, 0x05);
         assert_eq!(cpu.status.bits() & StatusFlags::ZERO.bits(), 0b00);
         assert!(cpu.status.bits() & StatusFlags::NEGATIVE.bits() == 0b00);
@@ -1220,7 +949,7 @@ mod test {
     #[test]
     fn test_0xa9_lda_zero_flag() {
         let mut cpu = new_test();
-        cpu.load_and_run(vec![0xA9, 0x00, 0x00]);
+        cpu.load_and_run(vec![0xA9, 0x00, 0x00]).unwrap();
         assert_eq!(cpu.register_a, 0x00);
         assert!(cpu.status.bits() & 0b0000_0010 == 0b10);
     }
@@ -1228,20 +957,22 @@ mod test {
     fn test_0xaa_tax_move_a_to_x() {
         let mut cpu = new_test();
         cpu.register_a = 10;
-        cpu.load_and_run(vec![0xa9, 0x0a, 0xAA, 0x00]);
+        cpu.load_and_run(vec![0xa9, 0x0a, 0xAA, 0x00]).unwrap();
         assert_eq!(cpu.register_x, 10);
     }
     #[test]
     fn test_5_ops_working_together() {
         let mut cpu = new_test();
-        cpu.load_and_run(vec![0xa9, 0xc0, 0xaa, 0xe8, 0x00]);
+        cpu.load_and_run(vec![0xa9, 0xc0, 0xaa, 0xe8, 0x00])
+            .unwrap();
         assert_eq!(cpu.register_x, 0xc1);
     }
     #[test]
     fn test_inx_overflow() {
         let mut cpu = new_test();
         cpu.register_x = 0xff;
-        cpu.load_and_run(vec![0xa9, 0xff, 0xaa, 0xe8, 0xe8, 0x00]);
+        cpu.load_and_run(vec![0xa9, 0xff, 0xaa, 0xe8, 0xe8, 0x00])
+            .unwrap();
         assert_eq!(cpu.register_x, 1);
     }
     #[test]
@@ -1250,7 +981,7 @@ mod test {
         cpu.load(vec![0xe6, 0x01, 0x00]);
         cpu.reset();
         cpu.mem_write(0x01, 0x01);
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         let value = cpu.mem_read(0x01);
         assert_eq!(value, 2);
     }
@@ -1260,14 +991,14 @@ mod test {
         cpu.load(vec![0xc8, 0x00]);
         cpu.reset();
         cpu.register_y = 0x01;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.register_y, 2);
     }
     #[test]
     fn test_lda_from_memory() {
         let mut cpu = new_test();
         cpu.mem_write(0x10, 0x55);
-        cpu.load_and_run(vec![0xa5, 0x10, 0x00]);
+        cpu.load_and_run(vec![0xa5, 0x10, 0x00]).unwrap();
         assert_eq!(cpu.register_a, 0x55);
     }
     #[test]
@@ -1275,7 +1006,7 @@ mod test {
         let mut cpu = new_test();
         cpu.load(vec![0xA2, 0x10, 0x00]);
         cpu.reset();
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.register_x, 0x10);
     }
     #[test]
@@ -1283,7 +1014,7 @@ mod test {
         let mut cpu = new_test();
         cpu.load(vec![0xa0, 0xff, 0x00]);
         cpu.reset();
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.register_y, 0xff);
     }
 
@@ -1294,7 +1025,7 @@ mod test {
         cpu.reset();
         cpu.register_a = 0x01;
         cpu.mem_write(0x8001, 0x01);
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0001, 0);
         assert_eq!(cpu.register_a, 0x2);
     }
@@ -1306,7 +1037,7 @@ mod test {
         cpu.status = StatusFlags::CARRY;
         cpu.register_a = 0x01;
         cpu.mem_write(0x8001, 0x01);
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0001, 0);
         assert_eq!(cpu.register_a, 0x3);
     }
@@ -1318,7 +1049,7 @@ mod test {
         cpu.reset();
         cpu.register_a = 0x01;
         cpu.mem_write(0x8001, 0xff);
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         // is carry flag is true
         // 0000_0000 ^ 0000_0001 = 0000_0001 & 1000_0000
         assert_eq!(cpu.status.bits() & 0b0000_0001, 1);
@@ -1333,7 +1064,7 @@ mod test {
         cpu.register_a = 0x7f;
         cpu.status = StatusFlags::CARRY;
         cpu.mem_write(0x8001, 0x7f);
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0001, 0);
         assert_eq!(cpu.status.bits() & 0b0100_0000, 0x40);
         assert_eq!(cpu.register_a, 0xff);
@@ -1347,7 +1078,7 @@ mod test {
         cpu.register_a = 0x50;
         cpu.status = StatusFlags::from_bits_truncate(0b00000000);
         cpu.mem_write(0x8001, 0x90);
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.register_a, 0xe0);
     }
 
@@ -1359,7 +1090,7 @@ mod test {
         cpu.register_a = 0b0101_0000; //80
         cpu.status = StatusFlags::from_bits_truncate(0b00000000);
         cpu.mem_write(0x8001, 0b1111_0000); // -112
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         // 0101_0000 + 1111_0000 = 1_0100_0000 => 0100_0000 cast as u8
         // occur overflow not negative value
         assert_eq!(cpu.status.bits() & 0b0000_0001, 1);
@@ -1372,7 +1103,7 @@ mod test {
         cpu.load(vec![0xe9, 0x10, 0x00]);
         cpu.reset();
         cpu.register_a = 0x20;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0001, 1);
         assert_eq!(cpu.register_a, 0x0f);
     }
@@ -1384,7 +1115,7 @@ mod test {
         cpu.reset();
         cpu.register_a = 0x20;
         cpu.status = StatusFlags::CARRY;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0001, 1);
         assert_eq!(cpu.register_a, 0x10);
     }
@@ -1395,7 +1126,7 @@ mod test {
         cpu.load(vec![0xe9, 0x00, 0x00]);
         cpu.reset();
         cpu.register_a = 0x0;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0000, 0);
         assert_eq!(cpu.register_a, 0xff);
     }
@@ -1406,7 +1137,7 @@ mod test {
         cpu.reset();
         cpu.register_a = 0x0;
         cpu.status = StatusFlags::CARRY;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0000, 0);
         assert_eq!(cpu.register_a, 0x0);
     }
@@ -1417,7 +1148,7 @@ mod test {
         cpu.reset();
         cpu.register_a = 0x00; // decimal: 80
                                // 0 - (70) = -70
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         // 0101_0000 + 1111_0000 = 1_0100_0000 => 0100_0000 cast as u8
         // occur overflow not negative value
         assert_eq!(cpu.status.bits() & 0b0000_0001, 0);
@@ -1432,7 +1163,7 @@ mod test {
         cpu.reset();
         cpu.register_a = 0xf0; // decimal: -70
         cpu.status = StatusFlags::CARRY;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         // 0101_0000 + 1111_0000 = 1_0100_0000 => 0100_0000 cast as u8
         // occur overflow not negative value
         assert_eq!(cpu.status.bits() & 0b0000_0001, 1);
@@ -1445,7 +1176,7 @@ mod test {
         cpu.load(vec![0xe9, 0x20, 0x00]);
         cpu.reset();
         cpu.register_a = 0x10;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0001, 0);
         assert_eq!(cpu.status.bits() & 0b1000_0000, 0x80);
         assert_eq!(cpu.register_a, 0xef);
@@ -1457,7 +1188,7 @@ mod test {
         cpu.reset();
         cpu.register_a = 0x10;
         cpu.status = StatusFlags::CARRY;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0001, 0);
         assert_eq!(cpu.status.bits() & 0b1000_0000, 0x80);
         assert_eq!(cpu.register_a, 0xf0);
@@ -1468,7 +1199,7 @@ mod test {
         cpu.load(vec![0xe9, 0x70, 0x00]);
         cpu.reset();
         cpu.register_a = 0x70;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0001, 0);
         assert_eq!(cpu.status.bits() & 0b1000_0000, 0x80);
         assert_eq!(cpu.register_a, 0xff);
@@ -1480,7 +1211,7 @@ mod test {
         cpu.reset();
         cpu.register_a = 0x70;
         cpu.status = StatusFlags::CARRY;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0001, 1);
         assert_eq!(cpu.status.bits() & 0b1000_0000, 0x0);
         assert_eq!(cpu.register_a, 0x00);
@@ -1492,7 +1223,7 @@ mod test {
         cpu.load(vec![0x29, 0x10, 0x00]);
         cpu.reset();
         cpu.register_a = 0x10;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.register_a, 0x10);
     }
     #[test]
@@ -1501,7 +1232,7 @@ mod test {
         cpu.load(vec![0x49, 0x10, 0x00]);
         cpu.reset();
         cpu.register_a = 0x08;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.register_a, 0x18);
     }
     #[test]
@@ -1510,7 +1241,7 @@ mod test {
         cpu.load(vec![0x09, 0x10, 0x00]);
         cpu.reset();
         cpu.register_a = 0x08;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.register_a, 0x18);
     }
     #[test]
@@ -1520,7 +1251,7 @@ mod test {
         cpu.reset();
         cpu.mem_write(0x01, 0x01);
         cpu.register_a = 0x01;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0010, 0);
         assert_eq!(cpu.status.bits() & 0b1000_0000, 0);
         assert_eq!(cpu.status.bits() & 0b0100_0000, 0);
@@ -1532,7 +1263,7 @@ mod test {
         cpu.reset();
         cpu.mem_write(0x01, 0x00);
         cpu.register_a = 0x01;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0010, 0b10);
         assert_eq!(cpu.status.bits() & 0b1000_0000, 0);
         assert_eq!(cpu.status.bits() & 0b0100_0000, 0);
@@ -1544,7 +1275,7 @@ mod test {
         cpu.reset();
         cpu.mem_write(0x01, 0x42);
         cpu.register_a = 0x01;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0010, 0b10);
         assert_eq!(cpu.status.bits() & 0b1000_0000, 0);
         assert_eq!(
@@ -1559,7 +1290,7 @@ mod test {
         cpu.reset();
         cpu.mem_write(0x01, 0xC2);
         cpu.register_a = 0x01;
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0010, 0b10);
         assert_eq!(cpu.status.bits() & 0b1000_0000, 0b1000_0000);
         assert_eq!(
@@ -1567,20 +1298,87 @@ mod test {
             StatusFlags::OVERFLOW.bits()
         );
     }
+    // run until the cpu lands on `addr`
+    fn run_to(cpu: &mut CPU<FlatBus>, addr: u16) {
+        cpu.run_with_callback(|cpu| cpu.halted = cpu.program_counter == addr)
+            .unwrap();
+    }
+    // same, with /IRQ asserted once the first instruction is fetched
+    fn run_to_with_irq(cpu: &mut CPU<FlatBus>, addr: u16) {
+        cpu.run_with_callback(|cpu| {
+            cpu.bus.irq = true;
+            cpu.halted = cpu.program_counter == addr;
+        })
+        .unwrap();
+    }
+
     #[test]
     fn test_brk() {
         let mut cpu = new_test();
         cpu.load(vec![0x00]);
+        cpu.bus.mem_write_u16(0xfffe, 0x9000);
+        cpu.reset();
+        run_to(&mut cpu, 0x9000);
+        assert_eq!(cpu.stack_pointer, 0xfa);
+        // PC + 2 and P with B set
+        assert_eq!(cpu.bus.mem_read_u16(0x01fc), 0x8002);
+        assert_eq!(cpu.bus.mem_read(0x01fb), 0b0011_0100);
+        assert!(cpu.status.contains(StatusFlags::INTERRUPT_DISABLE));
+        assert!(!cpu.status.contains(StatusFlags::BREAK));
+    }
+    #[test]
+    fn test_irq_waits_one_instruction_after_cli() {
+        let mut cpu = new_test();
+        // CLI, NOP, NOP
+        cpu.load(vec![0x58, 0xea, 0xea]);
+        cpu.bus.mem_write_u16(0xfffe, 0x9000);
+        cpu.reset();
+        run_to_with_irq(&mut cpu, 0x9000);
+        // the NOP after CLI still runs
+        assert_eq!(cpu.bus.mem_read_u16(0x01fc), 0x8002);
+        assert_eq!(cpu.bus.mem_read(0x01fb), 0b0010_0000);
+        assert!(cpu.status.contains(StatusFlags::INTERRUPT_DISABLE));
+    }
+    #[test]
+    fn test_irq_still_fires_right_after_sei() {
+        let mut cpu = new_test();
+        // SEI, NOP, NOP
+        cpu.load(vec![0x78, 0xea, 0xea]);
+        cpu.bus.mem_write_u16(0xfffe, 0x9000);
         cpu.reset();
-        cpu.run();
-        assert_eq!(cpu.status.bits() & 0b0001_0000, StatusFlags::BREAK.bits());
+        cpu.status.remove(StatusFlags::INTERRUPT_DISABLE);
+        cpu.irq_inhibit = false;
+        run_to_with_irq(&mut cpu, 0x9000);
+        // the poll after SEI still sees I clear
+        assert_eq!(cpu.bus.mem_read_u16(0x01fc), 0x8001);
+        assert_eq!(cpu.bus.mem_read(0x01fb), 0b0010_0100);
+    }
+    #[test]
+    fn test_nmi() {
+        let mut cpu = new_test();
+        cpu.load(vec![0xea, 0xea]);
+        cpu.bus.mem_write_u16(0xfffa, 0xa000);
+        cpu.reset();
+        let mut starts = vec![];
+        cpu.run_with_callback(|cpu| {
+            starts.push(cpu.bus.cycles);
+            if starts.len() == 1 {
+                cpu.bus.nmi_interrupt = Some(1);
+            }
+            cpu.halted = cpu.program_counter == 0xa000;
+        })
+        .unwrap();
+        // NOP then the 7 cycles of the interrupt sequence
+        assert_eq!(starts[1] - starts[0], 2 + 7);
+        assert_eq!(cpu.bus.mem_read_u16(0x01fc), 0x8001);
+        assert_eq!(cpu.bus.mem_read(0x01fb), 0b0010_0100);
     }
     #[test]
     fn test_sec() {
         let mut cpu = new_test();
         cpu.load(vec![0x38, 0x00]);
         cpu.reset();
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0001, StatusFlags::CARRY.bits());
     }
     #[test]
@@ -1588,7 +1386,7 @@ mod test {
         let mut cpu = new_test();
         cpu.load(vec![0x18, 0x00]);
         cpu.reset();
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0001, 0);
     }
     #[test]
@@ -1596,7 +1394,7 @@ mod test {
         let mut cpu = new_test();
         cpu.load(vec![0xf8, 0x00]);
         cpu.reset();
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_1000, 0b0000_1000);
     }
     #[test]
@@ -1605,7 +1403,7 @@ mod test {
         cpu.load(vec![0xD8, 0x00]);
         cpu.status = StatusFlags::from_bits_truncate(0b0000_1000);
         cpu.reset();
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_1000, 0);
     }
     #[test]
@@ -1613,7 +1411,7 @@ mod test {
         let mut cpu = new_test();
         cpu.load(vec![0x78, 0x00]);
         cpu.reset();
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0100, 0b0000_0100);
     }
     #[test]
@@ -1622,7 +1420,7 @@ mod test {
         cpu.load(vec![0x58, 0x00]);
         cpu.status = StatusFlags::from_bits_truncate(0b0000_0100);
         cpu.reset();
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0000_0100, 0);
     }
     #[test]
@@ -1631,7 +1429,7 @@ mod test {
         cpu.load(vec![0xB8, 0x00]);
         cpu.status = StatusFlags::from_bits_truncate(0b0100_0000);
         cpu.reset();
-        cpu.run();
+        cpu.run_until_brk().unwrap();
         assert_eq!(cpu.status.bits() & 0b0100_0000, 0);
     }
 }
@@ -0,0 +1,579 @@
+use super::*;
+
+// one handler per instruction, `run_with_callback` looks them up by opcode.
+// the addressing mode and length come from `OPCODES`.
+pub(super) type Handler<M> = fn(&mut CPU<M>, &OpCode);
+
+impl<M: CpuBus> CPU<M> {
+    pub(super) const DISPATCH: [Handler<M>; 256] = dispatch_table();
+}
+
+const fn set<M: CpuBus>(table: &mut [Handler<M>; 256], codes: &[u8], handler: Handler<M>) {
+    let mut i = 0;
+    while i < codes.len() {
+        table[codes[i] as usize] = handler;
+        i += 1;
+    }
+}
+
+const fn dispatch_table<M: CpuBus>() -> [Handler<M>; 256] {
+    let mut table: [Handler<M>; 256] = [jam; 256];
+    set(
+        &mut table,
+        &[0xa9, 0xa5, 0xb5, 0xad, 0xbd, 0xb9, 0xa1, 0xb1],
+        lda,
+    );
+    set(&mut table, &[0x0a], asl_accumulator);
+    set(&mut table, &[0x06, 0x16, 0x0e, 0x1e], asl);
+    set(&mut table, &[0x4a], lsr_accumulator);
+    set(&mut table, &[0x46, 0x56, 0x4e, 0x5e], lsr);
+    set(&mut table, &[0x6a], ror_accumulator);
+    set(&mut table, &[0x66, 0x76, 0x6e, 0x7e], ror);
+    set(&mut table, &[0x2a], rol_accumulator);
+    set(&mut table, &[0x26, 0x36, 0x2e, 0x3e], rol);
+    set(&mut table, &[0xa2, 0xa6, 0xb6, 0xae, 0xbe], ldx);
+    set(&mut table, &[0xa0, 0xa4, 0xb4, 0xac, 0xbc], ldy);
+    set(&mut table, &[0xaa], tax);
+    set(&mut table, &[0x8a], txa);
+    set(&mut table, &[0xa8], tay);
+    set(&mut table, &[0x98], tya);
+    set(&mut table, &[0xe6, 0xf6, 0xee, 0xfe], inc);
+    set(&mut table, &[0xe8], inx);
+    set(&mut table, &[0xc8], iny);
+    set(&mut table, &[0xc6, 0xd6, 0xce, 0xde], dec);
+    set(&mut table, &[0xca], dex);
+    set(&mut table, &[0x88], dey);
+    set(&mut table, &[0xb0], bcs);
+    set(&mut table, &[0x90], bcc);
+    set(&mut table, &[0xf0], beq);
+    set(&mut table, &[0xd0], bne);
+    set(&mut table, &[0x70], bvs);
+    set(&mut table, &[0x50], bvc);
+    set(&mut table, &[0x10], bpl);
+    set(&mut table, &[0x30], bmi);
+    set(&mut table, &[0xba], tsx);
+    set(&mut table, &[0x48], pha);
+    set(&mut table, &[0x68], pla);
+    set(&mut table, &[0x08], php);
+    set(&mut table, &[0x28], plp);
+    set(&mut table, &[0x9a], txs);
+    set(&mut table, &[0x4c], jmp_absolute);
+    set(&mut table, &[0x6c], jmp_indirect);
+    set(&mut table, &[0x20], jsr);
+    set(&mut table, &[0x60], rts);
+    set(&mut table, &[0x40], rti);
+    set(
+        &mut table,
+        &[0xc9, 0xc5, 0xd5, 0xcd, 0xdd, 0xd9, 0xc1, 0xd1],
+        cmp,
+    );
+    set(&mut table, &[0xe0, 0xe4, 0xec], cpx);
+    set(&mut table, &[0xc0, 0xc4, 0xcc], cpy);
+    set(&mut table, &[0x85, 0x95, 0x8d, 0x9d, 0x99, 0x81, 0x91], sta);
+    set(&mut table, &[0x86, 0x96, 0x8e], stx);
+    set(&mut table, &[0x84, 0x94, 0x8c], sty);
+    set(
+        &mut table,
+        &[0x69, 0x65, 0x75, 0x6d, 0x7d, 0x79, 0x61, 0x71],
+        adc,
+    );
+    set(
+        &mut table,
+        &[0xe9, 0xe5, 0xf5, 0xed, 0xfd, 0xf9, 0xe1, 0xf1],
+        sbc,
+    );
+    set(
+        &mut table,
+        &[0x29, 0x25, 0x35, 0x2d, 0x3d, 0x39, 0x21, 0x31],
+        and,
+    );
+    set(
+        &mut table,
+        &[0x49, 0x45, 0x55, 0x4d, 0x5d, 0x59, 0x41, 0x51],
+        eor,
+    );
+    set(
+        &mut table,
+        &[0x09, 0x05, 0x15, 0x0d, 0x1d, 0x19, 0x01, 0x11],
+        ora,
+    );
+    set(&mut table, &[0x24, 0x2c], bit);
+    set(&mut table, &[0x38], sec);
+    set(&mut table, &[0x18], clc);
+    set(&mut table, &[0xf8], sed);
+    set(&mut table, &[0xd8], cld);
+    set(&mut table, &[0x78], sei);
+    set(&mut table, &[0x58], cli);
+    set(&mut table, &[0xb8], clv);
+    set(&mut table, &[0xea, 0x1a, 0x3a, 0x5a, 0x7a, 0xda, 0xfa], nop);
+    set(&mut table, &[0x00], brk);
+    set(&mut table, &[0xc7, 0xd7, 0xcf, 0xdf, 0xdb, 0xc3, 0xd3], dcp);
+    set(&mut table, &[0x27, 0x37, 0x2f, 0x3f, 0x3b, 0x23, 0x33], rla);
+    set(&mut table, &[0x07, 0x17, 0x0f, 0x1f, 0x1b, 0x03, 0x13], slo);
+    set(&mut table, &[0x47, 0x57, 0x4f, 0x5f, 0x5b, 0x43, 0x53], sre);
+    set(
+        &mut table,
+        &[
+            0x80, 0x82, 0x89, 0xc2, 0xe2, 0x04, 0x44, 0x64, 0x14, 0x34, 0x54, 0x74, 0xd4, 0xf4,
+            0x0c, 0x1c, 0x3c, 0x5c, 0x7c, 0xdc, 0xfc,
+        ],
+        nop_read,
+    );
+    set(&mut table, &[0xcb], axs);
+    set(&mut table, &[0x6b], arr);
+    set(&mut table, &[0xeb], usbc);
+    set(&mut table, &[0x0b, 0x2b], anc);
+    set(&mut table, &[0x4b], alr);
+    set(&mut table, &[0x67, 0x77, 0x6f, 0x7f, 0x7b, 0x63, 0x73], rra);
+    set(&mut table, &[0xe7, 0xf7, 0xef, 0xff, 0xfb, 0xe3, 0xf3], isb);
+    set(&mut table, &[0xa7, 0xb7, 0xaf, 0xbf, 0xa3, 0xb3], lax);
+    set(&mut table, &[0x87, 0x97, 0x8f, 0x83], sax);
+    set(&mut table, &[0xab], lxa);
+    set(&mut table, &[0x8b], xaa);
+    set(&mut table, &[0xbb], las);
+    set(&mut table, &[0x9b], tas);
+    set(&mut table, &[0x93, 0x9f], ahx);
+    set(&mut table, &[0x9e], shx);
+    set(&mut table, &[0x9c], shy);
+    set(
+        &mut table,
+        &[
+            0x02, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xb2, 0xd2, 0xf2,
+        ],
+        jam,
+    );
+    table
+}
+
+fn lda<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.lda(&op.mode);
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn asl_accumulator<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.asl_accumulator();
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn asl<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let value = cpu.asl(&op.mode);
+    cpu.update_zero_and_negative_flags(value);
+}
+
+fn lsr_accumulator<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.lsr_accumulator();
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn lsr<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let value = cpu.lsr(&op.mode);
+    cpu.update_zero_and_negative_flags(value);
+}
+
+fn ror_accumulator<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.ror_accumulator();
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn ror<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let value = cpu.ror(&op.mode);
+    cpu.update_zero_and_negative_flags(value);
+}
+
+fn rol_accumulator<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.rol_accumulator();
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn rol<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let value = cpu.rol(&op.mode);
+    cpu.update_zero_and_negative_flags(value);
+}
+
+fn ldx<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.ldx(&op.mode);
+    cpu.update_zero_and_negative_flags(cpu.register_x);
+}
+
+fn ldy<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.ldy(&op.mode);
+    cpu.update_zero_and_negative_flags(cpu.register_y);
+}
+
+fn tax<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.tax();
+    cpu.update_zero_and_negative_flags(cpu.register_x);
+}
+
+fn txa<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.txa();
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn tay<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.tay();
+    cpu.update_zero_and_negative_flags(cpu.register_y);
+}
+
+fn tya<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.tya();
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn inc<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.inc(&op.mode);
+}
+
+fn inx<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.inx();
+    cpu.update_zero_and_negative_flags(cpu.register_x);
+}
+
+fn iny<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.iny();
+    cpu.update_zero_and_negative_flags(cpu.register_y);
+}
+
+fn dec<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.dec(&op.mode);
+}
+
+fn dex<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.dex();
+    cpu.update_zero_and_negative_flags(cpu.register_x);
+}
+
+fn dey<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.dey();
+    cpu.update_zero_and_negative_flags(cpu.register_y);
+}
+
+fn bcs<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.branch(cpu.status.contains(StatusFlags::CARRY), &op.mode);
+}
+
+fn bcc<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.branch(!cpu.status.contains(StatusFlags::CARRY), &op.mode);
+}
+
+fn beq<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.branch(cpu.status.contains(StatusFlags::ZERO), &op.mode);
+}
+
+fn bne<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.branch(!cpu.status.contains(StatusFlags::ZERO), &op.mode);
+}
+
+fn bvs<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.branch(cpu.status.contains(StatusFlags::OVERFLOW), &op.mode);
+}
+
+fn bvc<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.branch(!cpu.status.contains(StatusFlags::OVERFLOW), &op.mode);
+}
+
+fn bpl<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.branch(!cpu.status.contains(StatusFlags::NEGATIVE), &op.mode);
+}
+
+fn bmi<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.branch(cpu.status.contains(StatusFlags::NEGATIVE), &op.mode);
+}
+
+fn tsx<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.tsx();
+    cpu.update_zero_and_negative_flags(cpu.register_x);
+}
+
+fn pha<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.pha();
+}
+
+fn pla<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.pla();
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn php<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.php();
+}
+
+fn plp<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.plp();
+}
+
+fn txs<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.txs();
+}
+
+fn jmp_absolute<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.jmp();
+}
+
+fn jmp_indirect<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.jmp_indirect();
+}
+
+fn jsr<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.jsr();
+}
+
+fn rts<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.rts();
+}
+
+fn rti<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.rti();
+}
+
+fn cmp<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.cmp(&op.mode, cpu.register_a);
+}
+
+fn cpx<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.cmp(&op.mode, cpu.register_x);
+}
+
+fn cpy<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.cmp(&op.mode, cpu.register_y);
+}
+
+fn sta<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.sta(&op.mode);
+}
+
+fn stx<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.stx(&op.mode);
+}
+
+fn sty<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.sty(&op.mode);
+}
+
+fn adc<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.adc(&op.mode);
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn sbc<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.sbc(&op.mode);
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn and<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.and(&op.mode);
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn eor<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.eor(&op.mode);
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn ora<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.ora(&op.mode);
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn bit<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.bit(&op.mode);
+}
+
+fn sec<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.sec();
+}
+
+fn clc<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.clc();
+}
+
+fn sed<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.sed();
+}
+
+fn cld<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.cld();
+}
+
+fn sei<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.sei();
+}
+
+fn cli<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.cli();
+}
+
+fn clv<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    cpu.clv();
+}
+
+fn nop<M: CpuBus>(_cpu: &mut CPU<M>, _op: &OpCode) {}
+
+fn brk<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    // the byte after BRK is skipped, it returns to PC + 2
+    cpu.program_counter = cpu.program_counter.wrapping_add(1);
+    cpu.interrupt_sequence(Interrupt::BRK);
+}
+
+// unofficial opcodes
+
+fn dcp<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let (addr, mut data) = cpu.read_modify_write_operand(&op.mode);
+    data = data.wrapping_sub(1);
+    cpu.mem_write(addr, data);
+    cpu.status.set(StatusFlags::CARRY, data <= cpu.register_a);
+
+    cpu.update_zero_and_negative_flags(cpu.register_a.wrapping_sub(data));
+}
+
+fn rla<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.rol(&op.mode);
+    cpu.register_a &= data;
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn slo<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.asl(&op.mode);
+    cpu.register_a |= data;
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn sre<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.lsr(&op.mode);
+    cpu.register_a ^= data;
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+// *NOP with an operand still reads it
+fn nop_read<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.read_operand(&op.mode);
+}
+
+fn axs<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.read_operand(&op.mode);
+    let ax = cpu.register_a & cpu.register_x;
+    let result = ax.wrapping_sub(data);
+    cpu.status.set(StatusFlags::CARRY, ax >= data);
+    cpu.update_zero_and_negative_flags(result);
+    cpu.register_x = result;
+}
+
+fn arr<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.read_operand(&op.mode);
+    cpu.register_a &= data;
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+    cpu.ror_accumulator();
+
+    let result = cpu.register_a;
+    let bit_5 = (result >> 5) & 1;
+    let bit_6 = (result >> 6) & 1;
+    if bit_6 == 1 {
+        cpu.status |= StatusFlags::CARRY;
+    } else {
+        cpu.status &= !StatusFlags::CARRY;
+    }
+
+    if bit_5 ^ bit_6 == 1 {
+        cpu.status |= StatusFlags::OVERFLOW;
+    } else {
+        cpu.status &= !StatusFlags::OVERFLOW;
+    }
+
+    cpu.update_zero_and_negative_flags(result);
+}
+
+// *SBC $EB, same as SBC #imm
+fn usbc<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.read_operand(&op.mode);
+    cpu.sub_from_register_a(data);
+}
+
+fn anc<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.read_operand(&op.mode);
+    cpu.register_a &= data;
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+    if cpu.status.contains(StatusFlags::NEGATIVE) {
+        cpu.status.insert(StatusFlags::CARRY);
+    } else {
+        cpu.status.remove(StatusFlags::CARRY);
+    }
+}
+
+fn alr<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.read_operand(&op.mode);
+    cpu.register_a &= data;
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+    cpu.lsr_accumulator();
+}
+
+fn rra<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.ror(&op.mode);
+    cpu.add_to_register_a(data);
+}
+
+fn isb<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.inc(&op.mode);
+    cpu.sub_from_register_a(data);
+}
+
+// *JAM locks the cpu up, see `JamPolicy`
+fn jam<M: CpuBus>(cpu: &mut CPU<M>, _op: &OpCode) {
+    // the cpu stays on the JAM opcode
+    cpu.program_counter = cpu.program_counter.wrapping_sub(1);
+    match cpu.jam_policy {
+        JamPolicy::Reset => cpu.reset(),
+        JamPolicy::Halt | JamPolicy::Error => cpu.jammed = true,
+    }
+}
+
+fn lax<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.read_operand(&op.mode);
+    cpu.register_a = data;
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+    cpu.register_x = cpu.register_a;
+}
+
+fn sax<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.register_a & cpu.register_x;
+    let addr = cpu.write_operand_address(&op.mode);
+    cpu.mem_write(addr, data);
+}
+
+fn lxa<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.read_operand(&op.mode);
+    cpu.register_a = (cpu.register_a | LXA_MAGIC) & data;
+    cpu.register_x = cpu.register_a;
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn xaa<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let data = cpu.read_operand(&op.mode);
+    cpu.register_a = (cpu.register_a | XAA_MAGIC) & cpu.register_x & data;
+    cpu.update_zero_and_negative_flags(cpu.register_a);
+}
+
+fn las<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    let mut data = cpu.read_operand(&op.mode);
+    data &= cpu.stack_pointer;
+    cpu.register_a = data;
+    cpu.register_x = data;
+    cpu.stack_pointer = data;
+    cpu.update_zero_and_negative_flags(data);
+}
+
+fn tas<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.stack_pointer = cpu.register_a & cpu.register_x;
+    cpu.unstable_store(&op.mode, cpu.stack_pointer);
+}
+
+fn ahx<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.unstable_store(&op.mode, cpu.register_a & cpu.register_x);
+}
+
+fn shx<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.unstable_store(&op.mode, cpu.register_x);
+}
+
+fn shy<M: CpuBus>(cpu: &mut CPU<M>, op: &OpCode) {
+    cpu.unstable_store(&op.mode, cpu.register_y);
+}
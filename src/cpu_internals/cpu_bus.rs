@@ -0,0 +1,84 @@
+use crate::cartridge::mem::Mem;
+
+// what the 6502 core needs from the system it is plugged into.
+// the NES `Bus` is one implementation, `FlatBus` is a plain 64KiB memory
+// to run and test 6502 code without a cartridge.
+pub trait CpuBus: Mem {
+    /// advance the rest of the system by `cycles` cpu cycles.
+    fn tick(&mut self, cycles: u8);
+    fn poll_nmi_status(&mut self) -> Option<u8>;
+    /// level of the /IRQ line, true while any source asserts it.
+    fn poll_irq_status(&mut self) -> bool;
+
+    /// what a read of `addr` would return, without its side effects
+    /// (latches, buffers, shift registers). for the trace and debugging tools.
+    fn peek(&self, addr: u16) -> u8;
+    /// store `data` at `addr` for debugging tools: memory (cartridge included)
+    /// is changed in place, registers with side effects are left alone.
+    fn poke(&mut self, addr: u16, data: u8);
+    /// cpu cycles since power on.
+    fn cycles(&self) -> usize;
+    /// (scanline, dot) of the ppu, `None` when there is no ppu on the bus.
+    fn ppu_position(&self) -> Option<(u16, usize)>;
+    /// the cpu is about to fetch the opcode at `addr`: the bytes of the
+    /// instruction are code. for the code/data logger, ignored by default.
+    fn fetch_instruction(&mut self, _addr: u16) {}
+}
+
+pub struct FlatBus {
+    memory: Box<[u8; 0x10000]>,
+    pub cycles: usize,
+    // interrupt lines driven by hand in tests
+    pub nmi_interrupt: Option<u8>,
+    pub irq: bool,
+}
+
+impl FlatBus {
+    pub fn new() -> Self {
+        FlatBus {
+            memory: Box::new([0; 0x10000]),
+            cycles: 0,
+            nmi_interrupt: None,
+            irq: false,
+        }
+    }
+}
+
+impl Default for FlatBus {
+    fn default() -> Self {
+        FlatBus::new()
+    }
+}
+
+impl Mem for FlatBus {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        self.memory[addr as usize]
+    }
+    fn mem_write(&mut self, addr: u16, data: u8) {
+        self.memory[addr as usize] = data;
+    }
+}
+
+impl CpuBus for FlatBus {
+    fn tick(&mut self, cycles: u8) {
+        self.cycles += cycles as usize;
+    }
+    fn poll_nmi_status(&mut self) -> Option<u8> {
+        self.nmi_interrupt.take()
+    }
+    fn poll_irq_status(&mut self) -> bool {
+        self.irq
+    }
+    fn peek(&self, addr: u16) -> u8 {
+        self.memory[addr as usize]
+    }
+    fn poke(&mut self, addr: u16, data: u8) {
+        self.memory[addr as usize] = data;
+    }
+    fn cycles(&self) -> usize {
+        self.cycles
+    }
+    fn ppu_position(&self) -> Option<(u16, usize)> {
+        None
+    }
+}
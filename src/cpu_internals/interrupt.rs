@@ -0,0 +1,73 @@
+use crate::cartridge::mem::{Mem, Stack};
+use crate::cpu_internals::cpu::CPU;
+use crate::cpu_internals::cpu_bus::CpuBus;
+use crate::cpu_internals::flags::StatusFlags;
+
+// https://www.nesdev.org/wiki/CPU_interrupts
+pub const NMI_VECTOR: u16 = 0xfffa;
+pub const RESET_VECTOR: u16 = 0xfffc;
+pub const IRQ_VECTOR: u16 = 0xfffe;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interrupt {
+    NMI,
+    IRQ,
+    BRK,
+}
+
+impl Interrupt {
+    pub fn vector(&self) -> u16 {
+        match self {
+            Interrupt::NMI => NMI_VECTOR,
+            Interrupt::IRQ | Interrupt::BRK => IRQ_VECTOR,
+        }
+    }
+
+    /// the B flag only exists in the status pushed on the stack,
+    /// it tells a BRK apart from an IRQ sharing the same vector.
+    pub fn break_flag(&self) -> bool {
+        *self == Interrupt::BRK
+    }
+}
+
+impl<M: CpuBus> CPU<M> {
+    // checked between two instructions.
+    // NMI is edge triggered and always wins, IRQ is a level masked by the I flag.
+    pub(crate) fn poll_interrupt(&mut self) -> Option<Interrupt> {
+        if self.bus.poll_nmi_status().is_some() {
+            return Some(Interrupt::NMI);
+        }
+        if self.bus.poll_irq_status() && !self.irq_inhibit {
+            return Some(Interrupt::IRQ);
+        }
+        None
+    }
+
+    // NMI and IRQ replace the next opcode fetch with two dummy reads,
+    // then run the same sequence as BRK.
+    pub(crate) fn hardware_interrupt(&mut self, interrupt: Interrupt) {
+        self.mem_read(self.program_counter);
+        self.mem_read(self.program_counter);
+        self.interrupt_sequence(interrupt);
+    }
+
+    // push PC and P, set I and jump through the vector (the last 5 cycles).
+    pub(crate) fn interrupt_sequence(&mut self, interrupt: Interrupt) {
+        self.stack_push_u16(self.program_counter);
+        // an NMI raised while PC is pushed hijacks a BRK or IRQ:
+        // the NMI vector is taken but the pushed B flag is kept.
+        let vector = match interrupt {
+            Interrupt::NMI => NMI_VECTOR,
+            _ if self.bus.poll_nmi_status().is_some() => NMI_VECTOR,
+            _ => interrupt.vector(),
+        };
+        let mut flag = self.status;
+        flag.set(StatusFlags::BREAK, interrupt.break_flag());
+        flag.set(StatusFlags::RESERVED, true);
+        self.stack_push(flag.bits());
+
+        self.status.insert(StatusFlags::INTERRUPT_DISABLE);
+        self.irq_inhibit = true;
+        self.program_counter = self.mem_read_u16(vector);
+    }
+}
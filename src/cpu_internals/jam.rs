@@ -0,0 +1,25 @@
+// what the cpu does on a KIL/JAM opcode ($02, $12, ... $F2).
+// the real 6502 locks up until reset, the rest of the system keeps running.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JamPolicy {
+    // freeze the cpu like the hardware does, the bus keeps being clocked
+    Halt,
+    // reset the cpu and keep going
+    Reset,
+    // return an error from `run_with_callback`
+    Error,
+}
+
+impl JamPolicy {
+    pub fn parse(value: &str) -> Result<JamPolicy, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "halt" => Ok(JamPolicy::Halt),
+            "reset" => Ok(JamPolicy::Reset),
+            "error" => Ok(JamPolicy::Error),
+            _ => Err(format!(
+                "jam policy expects halt, reset or error but got {}",
+                value
+            )),
+        }
+    }
+}
@@ -1,3 +1,6 @@
 pub mod cpu;
+pub mod cpu_bus;
 pub mod flags;
+pub mod interrupt;
+pub mod jam;
 pub mod opscodes;
@@ -0,0 +1,257 @@
+use std::fmt;
+
+use crate::cpu_internals::cpu::CPU;
+use crate::debugger::watch_bus::Access;
+
+/// parse `$c000`, `0xc000` or `49152`.
+pub fn parse_number(value: &str) -> Result<u16, String> {
+    let value = value.trim();
+    let result = if let Some(hex) = value.strip_prefix('$') {
+        u16::from_str_radix(hex, 16)
+    } else if let Some(hex) = value.strip_prefix("0x") {
+        u16::from_str_radix(hex, 16)
+    } else {
+        value.parse::<u16>()
+    };
+    result.map_err(|e| format!("invalid number {}: {}", value, e))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Register {
+    A,
+    X,
+    Y,
+    P,
+    SP,
+    PC,
+}
+
+impl Register {
+    pub fn parse(value: &str) -> Result<Register, String> {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "A" => Ok(Register::A),
+            "X" => Ok(Register::X),
+            "Y" => Ok(Register::Y),
+            "P" => Ok(Register::P),
+            "SP" => Ok(Register::SP),
+            "PC" => Ok(Register::PC),
+            _ => Err(format!("unknown register {}", value)),
+        }
+    }
+
+    pub fn value<M>(&self, cpu: &CPU<M>) -> u16 {
+        match self {
+            Register::A => cpu.register_a as u16,
+            Register::X => cpu.register_x as u16,
+            Register::Y => cpu.register_y as u16,
+            Register::P => cpu.status.bits() as u16,
+            Register::SP => cpu.stack_pointer as u16,
+            Register::PC => cpu.program_counter,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+// the two character operators first, `<` would match the start of `<=`
+const COMPARISONS: [(&str, Comparison); 6] = [
+    ("==", Comparison::Eq),
+    ("!=", Comparison::Ne),
+    ("<=", Comparison::Le),
+    (">=", Comparison::Ge),
+    ("<", Comparison::Lt),
+    (">", Comparison::Gt),
+];
+
+// `A == $10`, `SP < $80`, ...
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Condition {
+    pub register: Register,
+    pub comparison: Comparison,
+    pub value: u16,
+}
+
+impl Condition {
+    pub fn parse(value: &str) -> Result<Condition, String> {
+        let (start, operator, comparison) = COMPARISONS
+            .iter()
+            .find_map(|(operator, comparison)| {
+                value
+                    .find(operator)
+                    .map(|start| (start, *operator, *comparison))
+            })
+            .ok_or(format!("condition {} has no comparison", value))?;
+        Ok(Condition {
+            register: Register::parse(&value[..start])?,
+            comparison,
+            value: parse_number(&value[start + operator.len()..])?,
+        })
+    }
+
+    pub fn matches<M>(&self, cpu: &CPU<M>) -> bool {
+        let register = self.register.value(cpu);
+        match self.comparison {
+            Comparison::Eq => register == self.value,
+            Comparison::Ne => register != self.value,
+            Comparison::Lt => register < self.value,
+            Comparison::Le => register <= self.value,
+            Comparison::Gt => register > self.value,
+            Comparison::Ge => register >= self.value,
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = COMPARISONS
+            .iter()
+            .find(|(_, comparison)| *comparison == self.comparison)
+            .map_or("?", |(operator, _)| *operator);
+        write!(f, "{:?} {} ${:X}", self.register, operator, self.value)
+    }
+}
+
+// stops before the instruction at `addr` runs, anywhere when there is no address
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub addr: Option<u16>,
+    pub condition: Option<Condition>,
+}
+
+impl Breakpoint {
+    pub fn hit<M>(&self, cpu: &CPU<M>) -> bool {
+        self.addr.is_none_or(|addr| addr == cpu.program_counter)
+            && self.condition.is_none_or(|c| c.matches(cpu))
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "break")?;
+        if let Some(addr) = self.addr {
+            write!(f, " ${:04X}", addr)?;
+        }
+        if let Some(condition) = self.condition {
+            write!(f, " if {}", condition)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Execute,
+}
+
+impl WatchKind {
+    pub fn parse(value: &str) -> Result<WatchKind, String> {
+        match value {
+            "r" | "read" => Ok(WatchKind::Read),
+            "w" | "write" => Ok(WatchKind::Write),
+            "x" | "exec" => Ok(WatchKind::Execute),
+            _ => Err(format!("watch expects r, w or x but got {}", value)),
+        }
+    }
+}
+
+// a range of bus addresses, `start..=end`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watchpoint {
+    pub kind: WatchKind,
+    pub start: u16,
+    pub end: u16,
+}
+
+impl Watchpoint {
+    /// `kind` is r, w or x, `range` an address or `start-end`.
+    pub fn parse(kind: &str, range: &str) -> Result<Watchpoint, String> {
+        let kind = WatchKind::parse(kind)?;
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (parse_number(start)?, parse_number(end)?),
+            None => (parse_number(range)?, parse_number(range)?),
+        };
+        if start > end {
+            return Err(format!("empty watch range {}", range));
+        }
+        Ok(Watchpoint { kind, start, end })
+    }
+
+    pub fn contains(&self, addr: u16) -> bool {
+        (self.start..=self.end).contains(&addr)
+    }
+
+    /// does a bus access of the last instruction trigger this watchpoint.
+    pub fn matches(&self, addr: u16, access: Access) -> bool {
+        let kind = match access {
+            Access::Read => WatchKind::Read,
+            Access::Write => WatchKind::Write,
+        };
+        self.kind == kind && self.contains(addr)
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            WatchKind::Read => "read",
+            WatchKind::Write => "write",
+            WatchKind::Execute => "exec",
+        };
+        if self.start == self.end {
+            write!(f, "watch {} ${:04X}", kind, self.start)
+        } else {
+            write!(f, "watch {} ${:04X}-${:04X}", kind, self.start, self.end)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cpu_internals::cpu_bus::FlatBus;
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("$c000"), Ok(0xc000));
+        assert_eq!(parse_number("0x10"), Ok(0x10));
+        assert_eq!(parse_number("16"), Ok(16));
+        assert!(parse_number("$10000").is_err());
+        assert!(parse_number("c000").is_err());
+    }
+
+    #[test]
+    fn test_condition() {
+        let mut cpu = CPU::new(FlatBus::new());
+        cpu.register_x = 3;
+        let condition = Condition::parse("x>=3").unwrap();
+        assert_eq!(condition.register, Register::X);
+        assert_eq!(condition.comparison, Comparison::Ge);
+        assert!(condition.matches(&cpu));
+        assert!(!Condition::parse("X < $3").unwrap().matches(&cpu));
+        assert_eq!(condition.to_string(), "X >= $3");
+
+        assert!(Condition::parse("Q==1").is_err());
+        assert!(Condition::parse("A 1").is_err());
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        let watch = Watchpoint::parse("w", "$2000-$2007").unwrap();
+        assert!(watch.matches(0x2006, Access::Write));
+        assert!(!watch.matches(0x2006, Access::Read));
+        assert!(!watch.matches(0x2008, Access::Write));
+        assert_eq!(watch.to_string(), "watch write $2000-$2007");
+        assert!(Watchpoint::parse("w", "$10-$1").is_err());
+        assert!(Watchpoint::parse("v", "$10").is_err());
+    }
+}
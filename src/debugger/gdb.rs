@@ -0,0 +1,388 @@
+// gdb remote serial protocol over tcp, so gdb and other remote debugging
+// front ends can drive the cpu. https://sourceware.org/gdb/current/onlinedocs/gdb.html/Remote-Protocol.html
+//
+// the 6502 has no register layout in gdb, this stub uses (`g`, `p`, `P`):
+//   0 A, 1 X, 2 Y, 3 P, 4 SP (one byte each), 5 PC (two bytes, little endian)
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use crate::cpu_internals::cpu::CPU;
+use crate::cpu_internals::cpu_bus::CpuBus;
+use crate::cpu_internals::flags::StatusFlags;
+use crate::debugger::watch_bus::{Access, WatchBus};
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+// the socket is checked for a ^C once every this many instructions
+const INTERRUPT_POLL_INTERVAL: usize = 0x400;
+const MAX_MEMORY_READ: usize = 0x1000;
+
+enum Packet {
+    Data(String),
+    // ^C sent outside a packet
+    Interrupt,
+}
+
+struct Connection {
+    stream: TcpStream,
+}
+
+impl Connection {
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let mut byte = [0u8];
+        self.stream.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    // `$data#checksum`, acked with `+` (or `-` to get it again)
+    fn read_packet(&mut self) -> io::Result<Packet> {
+        loop {
+            match self.read_byte()? {
+                b'$' => {}
+                0x03 => return Ok(Packet::Interrupt),
+                // acks of our packets and line noise
+                _ => continue,
+            }
+            let mut data = vec![];
+            loop {
+                match self.read_byte()? {
+                    b'#' => break,
+                    byte => data.push(byte),
+                }
+            }
+            let checksum = [self.read_byte()?, self.read_byte()?];
+            let expected = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|c| u8::from_str_radix(c, 16).ok());
+            if expected != Some(checksum_of(&data)) {
+                self.stream.write_all(b"-")?;
+                continue;
+            }
+            self.stream.write_all(b"+")?;
+            return Ok(Packet::Data(String::from_utf8_lossy(&data).to_string()));
+        }
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        self.stream.write_all(packet.as_bytes())
+    }
+
+    // a ^C waiting on the socket, without blocking the emulation
+    fn poll_interrupt(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let mut byte = [0u8];
+        let result = match self.stream.peek(&mut byte) {
+            Ok(0) => Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) if byte[0] == 0x03 => self.read_byte().map(|_| true),
+            Ok(_) => Ok(false),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e),
+        };
+        self.stream.set_nonblocking(false)?;
+        result
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn parse_hex(value: &str) -> Option<u16> {
+    u16::from_str_radix(value, 16).ok()
+}
+
+fn parse_hex_bytes(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// `addr,len` of the m, M and Z packets
+fn parse_range(value: &str) -> Option<(u16, usize)> {
+    let (addr, len) = value.split_once(',')?;
+    Some((parse_hex(addr)?, usize::from_str_radix(len, 16).ok()?))
+}
+
+// Z2 write, Z3 read, Z4 access
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Watchpoint {
+    kind: u8,
+    addr: u16,
+    len: usize,
+}
+
+impl Watchpoint {
+    fn matches(&self, addr: u16, access: Access) -> bool {
+        let kind = match access {
+            Access::Write => 2,
+            Access::Read => 3,
+        };
+        (self.kind == kind || self.kind == 4)
+            && addr >= self.addr
+            && ((addr - self.addr) as usize) < self.len
+    }
+
+    fn stop_reply(&self, addr: u16) -> String {
+        let name = match self.kind {
+            2 => "watch",
+            3 => "rwatch",
+            _ => "awatch",
+        };
+        format!("T{:02x}{}:{:04x};", SIGTRAP, name, addr)
+    }
+}
+
+enum State {
+    // waiting for the client in the first callback
+    Listening(TcpListener),
+    Running(Connection),
+    // the client left, the cpu runs freely
+    Detached,
+}
+
+// drive it from the run callback, like `Debugger`:
+//
+//     let mut stub = GdbStub::listen("127.0.0.1:6502")?;
+//     cpu.run_with_callback(|cpu| stub.on_instruction(cpu))
+pub struct GdbStub {
+    state: State,
+    breakpoints: Vec<u16>,
+    watchpoints: Vec<Watchpoint>,
+    stepping: bool,
+    instructions: usize,
+}
+
+impl GdbStub {
+    pub fn listen<A: ToSocketAddrs>(addr: A) -> Result<GdbStub, String> {
+        let listener =
+            TcpListener::bind(addr).map_err(|e| format!("can't listen for gdb: {}", e))?;
+        Ok(GdbStub {
+            state: State::Listening(listener),
+            breakpoints: vec![],
+            watchpoints: vec![],
+            stepping: false,
+            instructions: 0,
+        })
+    }
+
+    /// the address to connect to, e.g. when listening on port 0.
+    pub fn local_addr(&self) -> Option<std::net::SocketAddr> {
+        match &self.state {
+            State::Listening(listener) => listener.local_addr().ok(),
+            _ => None,
+        }
+    }
+
+    /// the run callback. blocks while the client has the cpu stopped.
+    pub fn on_instruction<M: CpuBus>(&mut self, cpu: &mut CPU<WatchBus<M>>) {
+        let state = std::mem::replace(&mut self.state, State::Detached);
+        let result = match state {
+            State::Listening(listener) => match listener.accept() {
+                // the cpu stays at the reset vector until the client resumes it
+                // packets are tiny, don't let them wait for more data
+                Ok((stream, _)) => stream
+                    .set_nodelay(true)
+                    .and_then(|_| self.serve(cpu, Connection { stream })),
+                Err(e) => Err(e),
+            },
+            State::Running(mut connection) => match self.stop_reply(cpu, &mut connection) {
+                Ok(Some(reply)) => connection
+                    .send(&reply)
+                    .and_then(|_| self.serve(cpu, connection)),
+                Ok(None) => Ok(State::Running(connection)),
+                Err(e) => Err(e),
+            },
+            state => Ok(state),
+        };
+        cpu.bus.clear_accesses();
+        self.state = result.unwrap_or_else(|e| {
+            eprintln!("gdb connection lost: {}", e);
+            State::Detached
+        });
+    }
+
+    // why the cpu stops before the instruction at PC, if it does
+    fn stop_reply<M: CpuBus>(
+        &mut self,
+        cpu: &CPU<WatchBus<M>>,
+        connection: &mut Connection,
+    ) -> io::Result<Option<String>> {
+        for (addr, access) in cpu.bus.accesses() {
+            if let Some(watch) = self.watchpoints.iter().find(|w| w.matches(*addr, *access)) {
+                return Ok(Some(watch.stop_reply(*addr)));
+            }
+        }
+        if self.breakpoints.contains(&cpu.program_counter) {
+            return Ok(Some(format!("T{:02x}swbreak:;", SIGTRAP)));
+        }
+        if self.stepping {
+            return Ok(Some(format!("S{:02x}", SIGTRAP)));
+        }
+        self.instructions += 1;
+        if self.instructions.is_multiple_of(INTERRUPT_POLL_INTERVAL)
+            && connection.poll_interrupt()?
+        {
+            return Ok(Some(format!("S{:02x}", SIGINT)));
+        }
+        Ok(None)
+    }
+
+    // answer packets until the client resumes, kills or leaves
+    fn serve<M: CpuBus>(
+        &mut self,
+        cpu: &mut CPU<WatchBus<M>>,
+        mut connection: Connection,
+    ) -> io::Result<State> {
+        loop {
+            let data = match connection.read_packet()? {
+                Packet::Data(data) => data,
+                // already stopped
+                Packet::Interrupt => continue,
+            };
+            let reply = match data.as_bytes().first() {
+                Some(b'c') | Some(b's') => {
+                    if let Some(addr) = data.get(1..).and_then(parse_hex) {
+                        cpu.program_counter = addr;
+                    }
+                    self.stepping = data.starts_with('s');
+                    return Ok(State::Running(connection));
+                }
+                Some(b'k') => {
+                    cpu.halted = true;
+                    return Ok(State::Detached);
+                }
+                Some(b'D') => {
+                    connection.send("OK")?;
+                    return Ok(State::Detached);
+                }
+                _ => self.handle(cpu, &data),
+            };
+            connection.send(&reply)?;
+        }
+    }
+
+    // packets answered while stopped, an empty reply means unsupported
+    fn handle<M: CpuBus>(&mut self, cpu: &mut CPU<WatchBus<M>>, data: &str) -> String {
+        if !data.is_char_boundary(1) {
+            return String::new();
+        }
+        let (command, args) = data.split_at(1);
+        let reply = match command {
+            "?" => Some(format!("S{:02x}", SIGTRAP)),
+            "g" => Some(hex(&registers(cpu))),
+            "G" => parse_hex_bytes(args).and_then(|bytes| {
+                let bytes: [u8; 7] = bytes.try_into().ok()?;
+                for (i, byte) in bytes.iter().enumerate().take(5) {
+                    set_register(cpu, i, &[*byte]);
+                }
+                set_register(cpu, 5, &bytes[5..]);
+                Some("OK".to_string())
+            }),
+            "p" => usize::from_str_radix(args, 16).ok().and_then(|n| {
+                let registers = registers(cpu);
+                match n {
+                    0..=4 => Some(hex(&registers[n..n + 1])),
+                    5 => Some(hex(&registers[5..7])),
+                    _ => None,
+                }
+            }),
+            "P" => args.split_once('=').and_then(|(n, value)| {
+                let n = usize::from_str_radix(n, 16).ok()?;
+                set_register(cpu, n, &parse_hex_bytes(value)?).then(|| "OK".to_string())
+            }),
+            "m" => parse_range(args).map(|(addr, len)| {
+                let bytes = (0..len.min(MAX_MEMORY_READ) as u16)
+                    .map(|i| cpu.bus.peek(addr.wrapping_add(i)))
+                    .collect::<Vec<u8>>();
+                hex(&bytes)
+            }),
+            "M" => args.split_once(':').and_then(|(range, value)| {
+                let (addr, len) = parse_range(range)?;
+                let bytes = parse_hex_bytes(value)?;
+                if bytes.len() != len {
+                    return None;
+                }
+                for (i, byte) in bytes.iter().enumerate() {
+                    cpu.bus.poke(addr.wrapping_add(i as u16), *byte);
+                }
+                Some("OK".to_string())
+            }),
+            "Z" | "z" => self.update_breakpoint(command == "Z", args),
+            "H" => Some("OK".to_string()),
+            "q" => {
+                return match args {
+                    a if a.starts_with("Supported") => "PacketSize=1000;swbreak+".to_string(),
+                    "Attached" => "1".to_string(),
+                    "C" => "QC1".to_string(),
+                    "fThreadInfo" => "m1".to_string(),
+                    "sThreadInfo" => "l".to_string(),
+                    _ => String::new(),
+                }
+            }
+            _ => return String::new(),
+        };
+        reply.unwrap_or_else(|| "E01".to_string())
+    }
+
+    // Z0/Z1 breakpoints (no difference here), Z2-Z4 watchpoints
+    fn update_breakpoint(&mut self, insert: bool, args: &str) -> Option<String> {
+        let (kind, range) = args.split_once(',')?;
+        let (addr, len) = parse_range(range)?;
+        match kind {
+            "0" | "1" => {
+                self.breakpoints.retain(|a| *a != addr);
+                if insert {
+                    self.breakpoints.push(addr);
+                }
+            }
+            "2" | "3" | "4" => {
+                let watch = Watchpoint {
+                    kind: kind.parse().ok()?,
+                    addr,
+                    len,
+                };
+                self.watchpoints.retain(|w| *w != watch);
+                if insert {
+                    self.watchpoints.push(watch);
+                }
+            }
+            _ => return Some(String::new()),
+        }
+        Some("OK".to_string())
+    }
+}
+
+fn registers<M>(cpu: &CPU<M>) -> [u8; 7] {
+    let [pc_lo, pc_hi] = cpu.program_counter.to_le_bytes();
+    [
+        cpu.register_a,
+        cpu.register_x,
+        cpu.register_y,
+        cpu.status.bits(),
+        cpu.stack_pointer,
+        pc_lo,
+        pc_hi,
+    ]
+}
+
+fn set_register<M>(cpu: &mut CPU<M>, n: usize, value: &[u8]) -> bool {
+    match (n, value) {
+        (0, [v]) => cpu.register_a = *v,
+        (1, [v]) => cpu.register_x = *v,
+        (2, [v]) => cpu.register_y = *v,
+        (3, [v]) => cpu.status = StatusFlags::from_bits_truncate(*v),
+        (4, [v]) => cpu.stack_pointer = *v,
+        (5, [lo, hi]) => cpu.program_counter = u16::from_le_bytes([*lo, *hi]),
+        _ => return false,
+    }
+    true
+}
@@ -0,0 +1,274 @@
+use crate::cpu_internals::cpu_bus::{CpuBus, FlatBus};
+
+// changed bytes are shown in reverse video
+const CHANGED_START: &str = "\x1b[7m";
+const CHANGED_END: &str = "\x1b[0m";
+// longer lists of addresses are counted, not printed in full
+const MAX_LISTED: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Space {
+    // what the cpu sees, through `peek` and `poke`
+    Cpu,
+    // pattern tables, nametables and palette, $0000-$3FFF
+    Ppu,
+    Oam,
+    PrgRom,
+    ChrRom,
+}
+
+const SPACES: [(&str, Space); 5] = [
+    ("cpu", Space::Cpu),
+    ("ppu", Space::Ppu),
+    ("oam", Space::Oam),
+    ("prg", Space::PrgRom),
+    ("chr", Space::ChrRom),
+];
+
+impl Space {
+    pub fn parse(value: &str) -> Result<Space, String> {
+        SPACES
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, space)| *space)
+            .ok_or(format!(
+                "unknown memory {}, expected cpu, ppu, oam, prg or chr",
+                value
+            ))
+    }
+
+    pub fn name(&self) -> &'static str {
+        SPACES
+            .iter()
+            .find(|(_, space)| space == self)
+            .map_or("?", |(name, _)| *name)
+    }
+}
+
+// every memory of the system the viewer can show and edit. the cpu space is
+// `peek`/`poke`, a bus without the other chips keeps the defaults.
+pub trait MemorySpaces: CpuBus {
+    /// bytes in `space`, 0 when the bus doesn't have it.
+    fn space_len(&self, space: Space) -> usize {
+        match space {
+            Space::Cpu => 0x10000,
+            _ => 0,
+        }
+    }
+    fn peek_space(&self, space: Space, addr: usize) -> u8 {
+        match space {
+            Space::Cpu => self.peek(addr as u16),
+            _ => 0,
+        }
+    }
+    fn poke_space(&mut self, space: Space, addr: usize, data: u8) {
+        if space == Space::Cpu {
+            self.poke(addr as u16, data);
+        }
+    }
+}
+
+impl MemorySpaces for FlatBus {}
+
+/// an offset in a space, `$1c000`, `0x1c000` or `114688`: ROMs go past $FFFF.
+pub fn parse_offset(value: &str) -> Result<usize, String> {
+    let value = value.trim();
+    let result = if let Some(hex) = value.strip_prefix('$') {
+        usize::from_str_radix(hex, 16)
+    } else if let Some(hex) = value.strip_prefix("0x") {
+        usize::from_str_radix(hex, 16)
+    } else {
+        value.parse::<usize>()
+    };
+    result.map_err(|e| format!("invalid number {}: {}", value, e))
+}
+
+/// bytes of a search or an edit: `A9 42`, `$A9 $42` or `A942`.
+pub fn parse_bytes(args: &[&str]) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+    for arg in args {
+        let hex = arg.strip_prefix('$').unwrap_or(arg);
+        if hex.is_empty() || hex.len() % 2 == 1 {
+            return Err(format!("{} is not a sequence of hex bytes", arg));
+        }
+        for i in (0..hex.len()).step_by(2) {
+            let byte = u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|e| format!("invalid byte in {}: {}", arg, e))?;
+            bytes.push(byte);
+        }
+    }
+    if bytes.is_empty() {
+        return Err("no bytes given".to_string());
+    }
+    Ok(bytes)
+}
+
+// hex dumps of the memory spaces, the bytes that changed since the last
+// frame highlighted.
+#[derive(Debug, Default)]
+pub struct MemoryViewer {
+    // each space as it was at the start of the current frame
+    snapshots: Vec<(Space, Vec<u8>)>,
+}
+
+impl MemoryViewer {
+    pub fn new() -> Self {
+        MemoryViewer::default()
+    }
+
+    /// remember the memory at the start of a frame.
+    pub fn new_frame<M: MemorySpaces>(&mut self, bus: &M) {
+        self.snapshots = SPACES
+            .iter()
+            .map(|(_, space)| {
+                let bytes = (0..bus.space_len(*space))
+                    .map(|addr| bus.peek_space(*space, addr))
+                    .collect();
+                (*space, bytes)
+            })
+            .collect();
+    }
+
+    fn changed<M: MemorySpaces>(&self, bus: &M, space: Space, addr: usize) -> bool {
+        self.snapshots
+            .iter()
+            .find(|(s, _)| *s == space)
+            .and_then(|(_, bytes)| bytes.get(addr))
+            .is_some_and(|old| *old != bus.peek_space(space, addr))
+    }
+
+    /// 16 bytes per line, the dump stops at the end of the space.
+    pub fn dump<M: MemorySpaces>(&self, bus: &M, space: Space, addr: usize, len: usize) -> String {
+        let end = (addr + len).min(bus.space_len(space));
+        let mut lines = vec![];
+        for line_start in (addr..end).step_by(16) {
+            let bytes = (line_start..end.min(line_start + 16))
+                .map(|addr| {
+                    let byte = bus.peek_space(space, addr);
+                    if self.changed(bus, space, addr) {
+                        format!("{}{:02X}{}", CHANGED_START, byte, CHANGED_END)
+                    } else {
+                        format!("{:02X}", byte)
+                    }
+                })
+                .collect::<Vec<String>>();
+            lines.push(format!("{:04X}  {}", line_start, bytes.join(" ")));
+        }
+        lines.join("\n")
+    }
+}
+
+/// write `bytes` from `addr` on, an error when they don't fit in the space.
+pub fn edit<M: MemorySpaces>(
+    bus: &mut M,
+    space: Space,
+    addr: usize,
+    bytes: &[u8],
+) -> Result<(), String> {
+    if addr + bytes.len() > bus.space_len(space) {
+        return Err(format!(
+            "${:X} is past the end of {} memory (${:X} bytes)",
+            addr + bytes.len() - 1,
+            space.name(),
+            bus.space_len(space)
+        ));
+    }
+    for (i, byte) in bytes.iter().enumerate() {
+        bus.poke_space(space, addr + i, *byte);
+    }
+    Ok(())
+}
+
+/// addresses where `pattern` starts.
+pub fn search<M: MemorySpaces>(bus: &M, space: Space, pattern: &[u8]) -> Vec<usize> {
+    let bytes = (0..bus.space_len(space))
+        .map(|addr| bus.peek_space(space, addr))
+        .collect::<Vec<u8>>();
+    bytes
+        .windows(pattern.len())
+        .enumerate()
+        .filter(|(_, window)| *window == pattern)
+        .map(|(addr, _)| addr)
+        .collect()
+}
+
+/// `3 matches: $0010 $0200 $0300`
+pub fn format_matches(matches: &[usize]) -> String {
+    let listed = matches
+        .iter()
+        .map(|addr| format!("${:04X}", addr))
+        .collect::<Vec<String>>();
+    format_addresses(&listed, "match", "matches")
+}
+
+/// `n <plural>: <items>`, shared by the memory and RAM searches.
+pub fn format_addresses(items: &[String], noun: &str, plural: &str) -> String {
+    let more = if items.len() > MAX_LISTED { " ..." } else { "" };
+    let listed = items[..items.len().min(MAX_LISTED)].join(" ");
+    match items.len() {
+        0 => format!("no {}", noun),
+        1 => format!("1 {}: {}", noun, listed),
+        n => format!("{} {}: {}{}", n, plural, listed, more),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cartridge::rom::test::test_rom;
+
+    #[test]
+    fn test_parse_bytes() {
+        assert_eq!(
+            parse_bytes(&["A9", "$42", "8d0002"]),
+            Ok(vec![0xa9, 0x42, 0x8d, 0x00, 0x02])
+        );
+        assert!(parse_bytes(&["A"]).is_err());
+        assert!(parse_bytes(&["XY"]).is_err());
+        assert!(parse_bytes(&[]).is_err());
+        assert_eq!(parse_offset("$1C000"), Ok(0x1c000));
+        assert!(parse_offset("1C000").is_err());
+        assert_eq!(Space::parse("chr"), Ok(Space::ChrRom));
+        assert!(Space::parse("apu").is_err());
+    }
+
+    #[test]
+    fn test_changed_bytes() {
+        let mut bus = FlatBus::new();
+        let mut viewer = MemoryViewer::new();
+        bus.poke(0x0201, 0x11);
+        assert_eq!(viewer.dump(&bus, Space::Cpu, 0x200, 3), "0200  00 11 00");
+        viewer.new_frame(&bus);
+        edit(&mut bus, Space::Cpu, 0x0202, &[0x22]).unwrap();
+        assert_eq!(
+            viewer.dump(&bus, Space::Cpu, 0x200, 3),
+            "0200  00 11 \x1b[7m22\x1b[0m"
+        );
+        assert_eq!(bus.space_len(Space::Ppu), 0);
+        assert_eq!(viewer.dump(&bus, Space::Oam, 0, 16), "");
+        assert_eq!(viewer.dump(&bus, Space::Cpu, 0xfffe, 4).lines().count(), 1);
+    }
+
+    #[test]
+    fn test_bus_spaces() {
+        let mut bus = Bus::new(test_rom(), |_, _, _| {});
+        edit(&mut bus, Space::Ppu, 0x3f10, &[0x0f]).unwrap();
+        assert_eq!(bus.ppu.palette_table[0], 0x0f);
+        edit(&mut bus, Space::Ppu, 0x2400, &[0x42]).unwrap();
+        assert_eq!(bus.peek_space(Space::Ppu, 0x2400), 0x42);
+        edit(&mut bus, Space::Oam, 0xff, &[0x07]).unwrap();
+        assert_eq!(bus.ppu.oam_data[0xff], 0x07);
+        assert!(edit(&mut bus, Space::Oam, 0xff, &[0x07, 0x08]).is_err());
+
+        edit(&mut bus, Space::PrgRom, 0x7ffe, &[0xa9, 0x42]).unwrap();
+        assert_eq!(bus.peek(0xfffe), 0xa9);
+        assert_eq!(search(&bus, Space::PrgRom, &[0xa9, 0x42]), [0x7ffe]);
+        assert_eq!(search(&bus, Space::Cpu, &[0xa9, 0x42]), [0xfffe]);
+        edit(&mut bus, Space::ChrRom, 0, &[0x33]).unwrap();
+        assert_eq!(bus.peek_space(Space::Ppu, 0), 0x33);
+
+        assert_eq!(format_matches(&[]), "no match");
+        assert_eq!(format_matches(&[0x10, 0x200]), "2 matches: $0010 $0200");
+    }
+}
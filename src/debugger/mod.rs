@@ -0,0 +1,639 @@
+pub mod breakpoint;
+pub mod gdb;
+pub mod memory;
+pub mod watch_bus;
+
+use std::io::{BufRead, Write};
+
+use crate::cheats::{self, Filter, RamSearch};
+use crate::cpu_internals::cpu::CPU;
+use crate::cpu_internals::cpu_bus::CpuBus;
+use crate::utils::disasm::disassemble_range;
+use crate::utils::symbols::Symbols;
+use crate::utils::trace::trace_with_symbols;
+use breakpoint::{parse_number, Breakpoint, Condition, WatchKind, Watchpoint};
+use memory::{MemorySpaces, MemoryViewer, Space};
+use watch_bus::WatchBus;
+
+const HELP: &str = "\
+c                       continue
+s [count]               step count instructions (1)
+n                       step over a JSR
+finish                  run until the current subroutine returns
+scanline <line>         run until the ppu enters line
+b [addr] [if <cond>]    break at addr, cond is like A==$10, SP<$80
+w r|w|x <addr>[-<end>]  stop on a read, write or execution in the range
+l                       list breakpoints and watchpoints
+d <number>              delete a breakpoint or watchpoint
+r                       registers
+x [mem:]<addr> [len]    dump memory, bytes changed since the last frame highlighted
+e [mem:]<addr> <bytes>  edit memory, bytes in hex like A9 42 or A942
+find [mem] <bytes>      search memory for a byte sequence
+                        mem is cpu (default), ppu, oam, prg or chr
+search [filter]         RAM search for cheats, starts over without a filter,
+                        filter is = <value>, >, <, changed or same
+u [addr] [count]        disassemble
+q                       quit
+an empty line repeats the last s, n, x or u";
+
+// the stepping and listing commands an empty line repeats
+const REPEATED_COMMANDS: [&str; 7] = ["s", "step", "n", "next", "x", "u", "dis"];
+
+const JSR: u8 = 0x20;
+const RTS: u8 = 0x60;
+const RTI: u8 = 0x40;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stop {
+    Break(Breakpoint),
+    Watch(Watchpoint),
+}
+
+// what to do until the next stop, breakpoints and watchpoints stop any of them
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Run,
+    // instructions to run before stopping
+    Step(usize),
+    // back from the JSR at the same stack depth
+    StepOver { return_addr: u16, stack_pointer: u8 },
+    // an RTS/RTI popped above this stack pointer
+    StepOut { stack_pointer: u8 },
+    Scanline(u16),
+}
+
+pub enum Reply {
+    // let the cpu run again
+    Resume,
+    Print(String),
+}
+
+// a gdb like prompt driven from `run_with_callback`:
+//
+//     let mut cpu = CPU::new(WatchBus::new(bus));
+//     let mut debugger = Debugger::new();
+//     cpu.run_with_callback(|cpu| debugger.on_instruction(cpu))
+pub struct Debugger {
+    pub stops: Vec<Stop>,
+    // labels shown in the trace and the disassembly, usable as addresses
+    pub symbols: Symbols,
+    mode: Mode,
+    // opcode and scanline seen by the last callback
+    previous_opcode: Option<u8>,
+    previous_scanline: Option<u16>,
+    last_command: String,
+    memory: MemoryViewer,
+    ram_search: Option<RamSearch>,
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Debugger::new()
+    }
+}
+
+impl Debugger {
+    /// starts stopped on the first instruction.
+    pub fn new() -> Self {
+        Debugger {
+            stops: vec![],
+            symbols: Symbols::new(),
+            mode: Mode::Step(0),
+            previous_opcode: None,
+            previous_scanline: None,
+            last_command: String::new(),
+            memory: MemoryViewer::new(),
+            ram_search: None,
+        }
+    }
+
+    /// the run callback, prompts on stdin/stdout when the cpu has to stop.
+    pub fn on_instruction<M: MemorySpaces>(&mut self, cpu: &mut CPU<WatchBus<M>>) {
+        if let Some(reason) = self.check(cpu) {
+            let stdin = std::io::stdin();
+            self.prompt(cpu, &reason, &mut stdin.lock(), &mut std::io::stdout());
+        }
+    }
+
+    /// why the cpu has to stop before the instruction at PC, if it has to.
+    pub fn check<M: MemorySpaces>(&mut self, cpu: &mut CPU<WatchBus<M>>) -> Option<String> {
+        let mut reasons = vec![];
+        for (i, stop) in self.stops.iter().enumerate() {
+            let hit = match stop {
+                Stop::Break(breakpoint) => breakpoint.hit(cpu),
+                Stop::Watch(watch) if watch.kind == WatchKind::Execute => {
+                    watch.contains(cpu.program_counter)
+                }
+                Stop::Watch(watch) => cpu
+                    .bus
+                    .accesses()
+                    .iter()
+                    .any(|(addr, access)| watch.matches(*addr, *access)),
+            };
+            if hit {
+                reasons.push(format!("#{} {}", i, stop));
+            }
+        }
+        cpu.bus.clear_accesses();
+
+        let scanline = cpu.bus.ppu_position().map(|(scanline, _)| scanline);
+        let previous_opcode = self.previous_opcode;
+        let previous_scanline = self.previous_scanline;
+        self.previous_opcode = Some(cpu.bus.peek(cpu.program_counter));
+        self.previous_scanline = scanline;
+        if let (Some(line), Some(previous)) = (scanline, previous_scanline) {
+            if line < previous {
+                self.memory.new_frame(&cpu.bus);
+            }
+        }
+
+        let done = match self.mode {
+            Mode::Run => false,
+            Mode::Step(0) => true,
+            Mode::Step(count) => {
+                self.mode = Mode::Step(count - 1);
+                false
+            }
+            Mode::StepOver {
+                return_addr,
+                stack_pointer,
+            } => cpu.program_counter == return_addr && cpu.stack_pointer == stack_pointer,
+            Mode::StepOut { stack_pointer } => {
+                matches!(previous_opcode, Some(RTS) | Some(RTI))
+                    && cpu.stack_pointer > stack_pointer
+            }
+            Mode::Scanline(line) => scanline == Some(line) && previous_scanline != Some(line),
+        };
+        if done {
+            reasons.push(match self.mode {
+                Mode::Scanline(line) => format!("scanline {}", line),
+                _ => "step".to_string(),
+            });
+        }
+        if reasons.is_empty() {
+            return None;
+        }
+        self.mode = Mode::Run;
+        Some(reasons.join(", "))
+    }
+
+    /// read and run commands until one resumes the cpu.
+    pub fn prompt<M, R, W>(
+        &mut self,
+        cpu: &mut CPU<WatchBus<M>>,
+        reason: &str,
+        input: &mut R,
+        output: &mut W,
+    ) where
+        M: MemorySpaces,
+        R: BufRead,
+        W: Write,
+    {
+        // the console is all the debugger has, nothing to do if it's gone
+        let _ = writeln!(
+            output,
+            "stopped: {}\n{}",
+            reason,
+            trace_with_symbols(cpu, &self.symbols)
+        );
+        loop {
+            let _ = write!(output, "> ");
+            let _ = output.flush();
+            let mut line = String::new();
+            match input.read_line(&mut line) {
+                Ok(0) | Err(_) => {
+                    cpu.halted = true;
+                    return;
+                }
+                Ok(_) => {}
+            }
+            let line = self.repeat_line(&line);
+            match self.execute(cpu, &line) {
+                Ok(Reply::Resume) => return,
+                Ok(Reply::Print(text)) => {
+                    let _ = writeln!(output, "{}", text);
+                }
+                Err(e) => {
+                    let _ = writeln!(output, "error: {}", e);
+                }
+            }
+        }
+    }
+
+    // an empty line repeats the last stepping or listing command, like gdb.
+    // commands changing breakpoints or searches are not repeated
+    fn repeat_line(&mut self, line: &str) -> String {
+        let line = match line.trim() {
+            "" => return self.last_command.clone(),
+            line => line.to_string(),
+        };
+        let command = line.split_whitespace().next().unwrap_or_default();
+        self.last_command = if REPEATED_COMMANDS.contains(&command) {
+            line.clone()
+        } else {
+            String::new()
+        };
+        line
+    }
+
+    pub fn execute<M: MemorySpaces>(
+        &mut self,
+        cpu: &mut CPU<WatchBus<M>>,
+        line: &str,
+    ) -> Result<Reply, String> {
+        let args = line.split_whitespace().collect::<Vec<&str>>();
+        let Some((&command, args)) = args.split_first() else {
+            return Ok(Reply::Print(String::new()));
+        };
+        match command {
+            "c" | "continue" => {
+                self.mode = Mode::Run;
+                Ok(Reply::Resume)
+            }
+            "s" | "step" => {
+                let count = match args.first() {
+                    Some(count) => parse_number(count)? as usize,
+                    None => 1,
+                };
+                if count == 0 {
+                    return Err("step needs at least 1 instruction".to_string());
+                }
+                self.mode = Mode::Step(count - 1);
+                Ok(Reply::Resume)
+            }
+            "n" | "next" => {
+                self.mode = if cpu.bus.peek(cpu.program_counter) == JSR {
+                    Mode::StepOver {
+                        return_addr: cpu.program_counter.wrapping_add(3),
+                        stack_pointer: cpu.stack_pointer,
+                    }
+                } else {
+                    Mode::Step(0)
+                };
+                Ok(Reply::Resume)
+            }
+            "finish" => {
+                self.mode = Mode::StepOut {
+                    stack_pointer: cpu.stack_pointer,
+                };
+                Ok(Reply::Resume)
+            }
+            "scanline" => {
+                if cpu.bus.ppu_position().is_none() {
+                    return Err("there is no ppu on the bus".to_string());
+                }
+                let line = args.first().ok_or("scanline needs a line")?;
+                self.mode = Mode::Scanline(parse_number(line)?);
+                Ok(Reply::Resume)
+            }
+            "b" | "break" => {
+                let (addr, condition) = match args {
+                    [] => (Some(cpu.program_counter), None),
+                    ["if", condition @ ..] => (None, Some(condition.join(""))),
+                    [addr] => (Some(self.parse_address(addr)?), None),
+                    [addr, "if", condition @ ..] => {
+                        (Some(self.parse_address(addr)?), Some(condition.join("")))
+                    }
+                    _ => return Err("usage: b [addr] [if <cond>]".to_string()),
+                };
+                let condition = condition.map(|c| Condition::parse(&c)).transpose()?;
+                Ok(self.add(Stop::Break(Breakpoint { addr, condition })))
+            }
+            "w" | "watch" => match args {
+                [kind, range] => Ok(self.add(Stop::Watch(Watchpoint::parse(kind, range)?))),
+                _ => Err("usage: w r|w|x <addr>[-<end>]".to_string()),
+            },
+            "l" | "list" => Ok(Reply::Print(
+                self.stops
+                    .iter()
+                    .enumerate()
+                    .map(|(i, stop)| format!("#{} {}", i, stop))
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            )),
+            "d" | "delete" => {
+                let index = parse_number(args.first().ok_or("delete needs a number")?)? as usize;
+                if index >= self.stops.len() {
+                    return Err(format!("no breakpoint #{}", index));
+                }
+                let stop = self.stops.remove(index);
+                Ok(Reply::Print(format!("deleted {}", stop)))
+            }
+            "r" | "regs" => Ok(Reply::Print(trace_with_symbols(cpu, &self.symbols))),
+            "x" => {
+                let (space, addr) =
+                    self.parse_location(args.first().ok_or("x needs an address")?)?;
+                let len = match args.get(1) {
+                    Some(len) => memory::parse_offset(len)?,
+                    None => 0x40,
+                };
+                Ok(Reply::Print(self.memory.dump(&cpu.bus, space, addr, len)))
+            }
+            "e" | "edit" => {
+                let (location, bytes) =
+                    args.split_first().ok_or("usage: e [mem:]<addr> <bytes>")?;
+                let (space, addr) = self.parse_location(location)?;
+                let bytes = memory::parse_bytes(bytes)?;
+                memory::edit(&mut cpu.bus, space, addr, &bytes)?;
+                Ok(Reply::Print(self.memory.dump(
+                    &cpu.bus,
+                    space,
+                    addr,
+                    bytes.len(),
+                )))
+            }
+            "find" => {
+                let (space, bytes) = match args.split_first() {
+                    Some((space, bytes)) if Space::parse(space).is_ok() => {
+                        (Space::parse(space)?, bytes)
+                    }
+                    _ => (Space::Cpu, args),
+                };
+                let bytes = memory::parse_bytes(bytes)?;
+                let matches = memory::search(&cpu.bus, space, &bytes);
+                Ok(Reply::Print(memory::format_matches(&matches)))
+            }
+            "search" => {
+                if args.is_empty() {
+                    let search = RamSearch::new(&cpu.bus);
+                    let count = search.candidates().len();
+                    self.ram_search = Some(search);
+                    return Ok(Reply::Print(format!("{} candidates", count)));
+                }
+                let filter = Filter::parse(args)?;
+                let search = self
+                    .ram_search
+                    .get_or_insert_with(|| RamSearch::new(&cpu.bus));
+                search.filter(&cpu.bus, filter);
+                Ok(Reply::Print(cheats::format_candidates(search.candidates())))
+            }
+            "u" | "dis" => {
+                let addr = match args.first() {
+                    Some(addr) => self.parse_address(addr)?,
+                    None => cpu.program_counter,
+                };
+                let count = match args.get(1) {
+                    Some(count) => parse_number(count)? as usize,
+                    None => 10,
+                };
+                let mut lines = vec![];
+                for i in disassemble_range(|addr| cpu.bus.peek(addr), addr, count) {
+                    if let Some(label) = self.symbols.get(i.addr) {
+                        lines.push(format!("{}:", label));
+                    }
+                    let marker = if i.addr == cpu.program_counter {
+                        ">"
+                    } else {
+                        " "
+                    };
+                    lines.push(format!("{} {}", marker, i.labeled(&self.symbols)));
+                }
+                Ok(Reply::Print(lines.join("\n")))
+            }
+            "q" | "quit" => {
+                cpu.halted = true;
+                Ok(Reply::Resume)
+            }
+            "h" | "help" => Ok(Reply::Print(HELP.to_string())),
+            _ => Err(format!("unknown command {}, h for help", command)),
+        }
+    }
+
+    // a label or a number
+    fn parse_address(&self, value: &str) -> Result<u16, String> {
+        match self.symbols.address_of(value) {
+            Some(addr) => Ok(addr),
+            None => parse_number(value),
+        }
+    }
+
+    // `ppu:$3F00`, `prg:$1C000`, or a cpu address
+    fn parse_location(&self, value: &str) -> Result<(Space, usize), String> {
+        match value.split_once(':') {
+            Some((space, addr)) => Ok((Space::parse(space)?, memory::parse_offset(addr)?)),
+            None => Ok((Space::Cpu, self.parse_address(value)? as usize)),
+        }
+    }
+
+    fn add(&mut self, stop: Stop) -> Reply {
+        let reply = Reply::Print(format!("#{} {}", self.stops.len(), stop));
+        self.stops.push(stop);
+        reply
+    }
+}
+
+impl std::fmt::Display for Stop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stop::Break(breakpoint) => write!(f, "{}", breakpoint),
+            Stop::Watch(watch) => write!(f, "{}", watch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cartridge::mem::Mem;
+    use crate::cpu_internals::cpu_bus::FlatBus;
+
+    // $8000: JSR $8010 / LDX #$01 / STX $0200 / (hang on BRK)
+    // $8010: LDA #$42 / RTS
+    fn cpu_with_program() -> CPU<WatchBus<FlatBus>> {
+        let mut bus = FlatBus::new();
+        for (i, byte) in [0x20, 0x10, 0x80, 0xa2, 0x01, 0x8e, 0x00, 0x02]
+            .iter()
+            .enumerate()
+        {
+            bus.mem_write(0x8000 + i as u16, *byte);
+        }
+        for (i, byte) in [0xa9, 0x42, 0x60].iter().enumerate() {
+            bus.mem_write(0x8010 + i as u16, *byte);
+        }
+        let mut cpu = CPU::new(WatchBus::new(bus));
+        cpu.program_counter = 0x8000;
+        cpu.stack_pointer = 0xfd;
+        cpu
+    }
+
+    fn command(debugger: &mut Debugger, cpu: &mut CPU<WatchBus<FlatBus>>, line: &str) -> String {
+        match debugger.execute(cpu, line).unwrap() {
+            Reply::Print(text) => text,
+            Reply::Resume => String::new(),
+        }
+    }
+
+    // run `line` then the cpu until the debugger stops it again, returns the reason.
+    // like the prompt returning from the callback, the instruction at PC runs first.
+    fn resume(debugger: &mut Debugger, cpu: &mut CPU<WatchBus<FlatBus>>, line: &str) -> String {
+        command(debugger, cpu, line);
+        let mut reason = String::new();
+        let mut first = true;
+        cpu.run_with_callback(|cpu| {
+            if std::mem::take(&mut first) {
+                return;
+            }
+            if let Some(r) = debugger.check(cpu) {
+                reason = r;
+                cpu.halted = true;
+            }
+        })
+        .unwrap();
+        reason
+    }
+
+    #[test]
+    fn test_step_and_step_over() {
+        let mut cpu = cpu_with_program();
+        let mut debugger = Debugger::new();
+        assert_eq!(debugger.check(&mut cpu).as_deref(), Some("step"));
+        assert_eq!(cpu.program_counter, 0x8000);
+
+        resume(&mut debugger, &mut cpu, "s");
+        assert_eq!(cpu.program_counter, 0x8010);
+
+        // back to the caller, then over the JSR from the start
+        resume(&mut debugger, &mut cpu, "finish");
+        assert_eq!(cpu.program_counter, 0x8003);
+        assert_eq!(cpu.register_a, 0x42);
+
+        cpu.program_counter = 0x8000;
+        resume(&mut debugger, &mut cpu, "n");
+        assert_eq!(cpu.program_counter, 0x8003);
+        assert_eq!(cpu.stack_pointer, 0xfd);
+
+        resume(&mut debugger, &mut cpu, "s 2");
+        assert_eq!(cpu.program_counter, 0x8008);
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut cpu = cpu_with_program();
+        let mut debugger = Debugger::new();
+        debugger.check(&mut cpu);
+
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "b $8012 if A == $42"),
+            "#0 break $8012 if A == $42"
+        );
+        assert_eq!(
+            resume(&mut debugger, &mut cpu, "c"),
+            "#0 break $8012 if A == $42"
+        );
+        assert_eq!(cpu.program_counter, 0x8012);
+
+        command(&mut debugger, &mut cpu, "d 0");
+        assert!(debugger.stops.is_empty());
+        command(&mut debugger, &mut cpu, "b if X==1");
+        resume(&mut debugger, &mut cpu, "c");
+        assert_eq!(cpu.program_counter, 0x8005);
+        assert!(debugger.execute(&mut cpu, "b $8000 if Q=1").is_err());
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let mut cpu = cpu_with_program();
+        let mut debugger = Debugger::new();
+        debugger.check(&mut cpu);
+
+        command(&mut debugger, &mut cpu, "w w $0200-$02ff");
+        command(&mut debugger, &mut cpu, "w x $8010");
+        assert_eq!(resume(&mut debugger, &mut cpu, "c"), "#1 watch exec $8010");
+
+        assert_eq!(
+            resume(&mut debugger, &mut cpu, "c"),
+            "#0 watch write $0200-$02FF"
+        );
+        // stopped after the STX
+        assert_eq!(cpu.program_counter, 0x8008);
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "l"),
+            "#0 watch write $0200-$02FF\n#1 watch exec $8010"
+        );
+    }
+
+    #[test]
+    fn test_views() {
+        let mut cpu = cpu_with_program();
+        let mut debugger = Debugger::new();
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "u $8000 2"),
+            "> 8000  20 10 80  JSR $8010\n  8003  A2 01     LDX #$01"
+        );
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "x $8010 3"),
+            "8010  A9 42 60"
+        );
+        assert!(debugger.execute(&mut cpu, "scanline 10").is_err());
+
+        debugger.symbols.insert(0x8010, "load_a");
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "u load_a 1"),
+            "load_a:\n  8010  A9 42     LDA #$42"
+        );
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "u $8000 1"),
+            "> 8000  20 10 80  JSR load_a"
+        );
+        assert!(debugger.execute(&mut cpu, "jump").is_err());
+    }
+
+    #[test]
+    fn test_memory_commands() {
+        let mut cpu = cpu_with_program();
+        let mut debugger = Debugger::new();
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "e $0300 a9 4260"),
+            "0300  A9 42 60"
+        );
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "find A9 42"),
+            "2 matches: $0300 $8010"
+        );
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "find cpu 6042"),
+            "no match"
+        );
+        // a flat bus has no ppu memory
+        assert_eq!(command(&mut debugger, &mut cpu, "x ppu:0"), "");
+        assert!(debugger.execute(&mut cpu, "e apu:0 00").is_err());
+        assert!(debugger.execute(&mut cpu, "e $ffff 00 00").is_err());
+    }
+
+    #[test]
+    fn test_ram_search() {
+        let mut cpu = cpu_with_program();
+        let mut debugger = Debugger::new();
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "search"),
+            "10240 candidates"
+        );
+        // JSR, LDA, RTS, LDX, STX
+        resume(&mut debugger, &mut cpu, "s 5");
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "search changed"),
+            "3 candidates: $01FC=02 $01FD=80 $0200=01"
+        );
+        assert_eq!(
+            command(&mut debugger, &mut cpu, "search = 1"),
+            "1 candidate: $0200=01"
+        );
+        assert!(debugger.execute(&mut cpu, "search ==").is_err());
+    }
+
+    #[test]
+    fn test_prompt() {
+        let mut cpu = cpu_with_program();
+        let mut debugger = Debugger::new();
+        let mut input = "b $8003\n\nu $8003 1\n\nbogus\nq\n".as_bytes();
+        let mut output = vec![];
+        debugger.prompt(&mut cpu, "step", &mut input, &mut output);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("stopped: step\n8000  20 10 80  JSR $8010"));
+        // an empty line doesn't add `b $8003` again but repeats `u`
+        assert_eq!(debugger.stops.len(), 1);
+        assert_eq!(output.matches("8003  A2 01").count(), 2);
+        assert!(output.contains("error: unknown command bogus"));
+        assert!(cpu.halted);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::cartridge::mem::Mem;
+use crate::cpu_internals::cpu_bus::CpuBus;
+use crate::debugger::memory::{MemorySpaces, Space};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+// sits between the cpu and the real bus and remembers every access,
+// dummy reads included since the hardware sees them too.
+// the debugger looks at them (and clears them) between two instructions.
+pub struct WatchBus<M> {
+    pub inner: M,
+    accesses: Vec<(u16, Access)>,
+}
+
+impl<M: CpuBus> WatchBus<M> {
+    pub fn new(inner: M) -> Self {
+        WatchBus {
+            inner,
+            accesses: vec![],
+        }
+    }
+
+    /// accesses since the last `clear_accesses`.
+    pub fn accesses(&self) -> &[(u16, Access)] {
+        &self.accesses
+    }
+
+    pub fn clear_accesses(&mut self) {
+        self.accesses.clear();
+    }
+}
+
+impl<M: CpuBus> Mem for WatchBus<M> {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        self.accesses.push((addr, Access::Read));
+        self.inner.mem_read(addr)
+    }
+    fn mem_write(&mut self, addr: u16, data: u8) {
+        self.accesses.push((addr, Access::Write));
+        self.inner.mem_write(addr, data);
+    }
+}
+
+impl<M: CpuBus> CpuBus for WatchBus<M> {
+    fn tick(&mut self, cycles: u8) {
+        self.inner.tick(cycles);
+    }
+    fn poll_nmi_status(&mut self) -> Option<u8> {
+        self.inner.poll_nmi_status()
+    }
+    fn poll_irq_status(&mut self) -> bool {
+        self.inner.poll_irq_status()
+    }
+    fn peek(&self, addr: u16) -> u8 {
+        self.inner.peek(addr)
+    }
+    fn poke(&mut self, addr: u16, data: u8) {
+        self.inner.poke(addr, data);
+    }
+    fn cycles(&self) -> usize {
+        self.inner.cycles()
+    }
+    fn ppu_position(&self) -> Option<(u16, usize)> {
+        self.inner.ppu_position()
+    }
+    fn fetch_instruction(&mut self, addr: u16) {
+        self.inner.fetch_instruction(addr);
+    }
+}
+
+impl<M: MemorySpaces> MemorySpaces for WatchBus<M> {
+    fn space_len(&self, space: Space) -> usize {
+        self.inner.space_len(space)
+    }
+    fn peek_space(&self, space: Space, addr: usize) -> u8 {
+        self.inner.peek_space(space, addr)
+    }
+    fn poke_space(&mut self, space: Space, addr: usize, data: u8) {
+        self.inner.poke_space(space, addr, data);
+    }
+}
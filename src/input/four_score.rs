@@ -0,0 +1,85 @@
+use crate::input::InputDevice;
+use crate::joypad::Joypad;
+
+// https://www.nesdev.org/wiki/Four_Score
+// a Four Score or an NES Satellite takes both ports, one of these in each.
+// a port reads 8 buttons of its first pad, 8 of its second one, then a
+// signature telling the game the adapter is there.
+pub struct FourScore {
+    // players 1 and 3 on port 1, 2 and 4 on port 2
+    pub pads: [Joypad; 2],
+    // read after the pads, first bit in bit 0
+    signature: u8,
+    strobe: bool,
+    index: u8,
+}
+
+impl FourScore {
+    pub fn new(port: usize) -> Self {
+        FourScore {
+            pads: [Joypad::new(), Joypad::new()],
+            // 0,0,0,1,0,0,0,0 on port 1 and 0,0,1,0,0,0,0,0 on port 2
+            signature: if port == 0 { 0b0000_1000 } else { 0b0000_0100 },
+            strobe: false,
+            index: 0,
+        }
+    }
+}
+
+impl InputDevice for FourScore {
+    fn write(&mut self, data: u8) {
+        self.strobe = data & 1 == 1;
+        if self.strobe {
+            self.index = 0;
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        let response = self.peek();
+        if !self.strobe && self.index < 24 {
+            self.index += 1;
+        }
+        response
+    }
+
+    fn peek(&self) -> u8 {
+        let byte = match self.index / 8 {
+            0 => self.pads[0].button_status.bits(),
+            1 => self.pads[1].button_status.bits(),
+            2 => self.signature,
+            _ => return 1,
+        };
+        (byte >> (self.index % 8)) & 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::joypad::JoypadButton;
+
+    fn report(four_score: &mut FourScore) -> Vec<u8> {
+        four_score.write(1);
+        four_score.write(0);
+        (0..26).map(|_| four_score.read()).collect()
+    }
+
+    #[test]
+    fn test_four_score() {
+        let mut port1 = FourScore::new(0);
+        port1.pads[0].set_button_pressed_status(JoypadButton::BUTTON_A, true);
+        port1.pads[1].set_button_pressed_status(JoypadButton::RIGHT, true);
+        let bits = report(&mut port1);
+        assert_eq!(bits[..8], [1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(bits[8..16], [0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(bits[16..24], [0, 0, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(bits[24..], [1, 1]);
+
+        let mut port2 = FourScore::new(1);
+        assert_eq!(report(&mut port2)[16..24], [0, 0, 1, 0, 0, 0, 0, 0]);
+
+        // the strobe held high keeps returning the first button
+        port1.write(1);
+        assert_eq!([port1.read(), port1.read()], [1, 1]);
+    }
+}
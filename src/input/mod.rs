@@ -0,0 +1,143 @@
+pub mod four_score;
+pub mod power_pad;
+pub mod vaus;
+pub mod zapper;
+
+use std::any::Any;
+
+use crate::joypad::Joypad;
+use crate::rendering::frame::Frame;
+use four_score::FourScore;
+use power_pad::PowerPad;
+use vaus::Vaus;
+use zapper::Zapper;
+
+// https://www.nesdev.org/wiki/Input_devices
+// something plugged in a controller port. every port sees the $4016 writes,
+// bit 0 is the strobe (OUT0). a read of $4016/$4017 gets the d0-d4 lines of
+// port 1/2, the bus fills the other bits.
+pub trait InputDevice: Any {
+    fn write(&mut self, data: u8);
+    fn read(&mut self) -> u8;
+    /// the bits the next read returns, without shifting anything.
+    fn peek(&self) -> u8;
+    /// called before a read with the picture drawn so far and the scanline
+    /// the ppu is on, for light guns.
+    fn observe(&mut self, _frame: &Frame, _scanline: u16) {}
+}
+
+// ports 1 and 2, $4016 and $4017
+pub type Ports = [Box<dyn InputDevice>; 2];
+
+/// the device as what it is, to script its inputs.
+pub fn device_mut<D: InputDevice>(device: &mut dyn InputDevice) -> Option<&mut D> {
+    (device as &mut dyn Any).downcast_mut::<D>()
+}
+
+/// the pad of `player` (0-3): a pad in a port, or one of a Four Score's.
+/// players 1 and 3 are on port 1, 2 and 4 on port 2.
+pub fn player_joypad(ports: &mut Ports, player: usize) -> Option<&mut Joypad> {
+    let device = ports.get_mut(player % 2)?.as_mut();
+    let slot = player / 2;
+    if slot == 0 && device_mut::<Joypad>(device).is_some() {
+        return device_mut::<Joypad>(device);
+    }
+    device_mut::<FourScore>(device)?.pads.get_mut(slot)
+}
+
+// an empty port, every line reads 0
+pub struct Unplugged;
+
+impl InputDevice for Unplugged {
+    fn write(&mut self, _data: u8) {}
+
+    fn read(&mut self) -> u8 {
+        0
+    }
+
+    fn peek(&self) -> u8 {
+        0
+    }
+}
+
+// what can go in port 2 from the command line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PortDevice {
+    Pad,
+    Zapper,
+    Vaus,
+    PowerPad,
+    Unplugged,
+}
+
+impl PortDevice {
+    pub fn parse(value: &str) -> Result<PortDevice, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "pad" => Ok(PortDevice::Pad),
+            "zapper" => Ok(PortDevice::Zapper),
+            "vaus" => Ok(PortDevice::Vaus),
+            "powerpad" => Ok(PortDevice::PowerPad),
+            "none" => Ok(PortDevice::Unplugged),
+            _ => Err(format!(
+                "port device expects pad, zapper, vaus, powerpad or none but got {}",
+                value
+            )),
+        }
+    }
+
+    pub fn create(&self) -> Box<dyn InputDevice> {
+        match self {
+            PortDevice::Pad => Box::new(Joypad::new()),
+            PortDevice::Zapper => Box::new(Zapper::new()),
+            PortDevice::Vaus => Box::new(Vaus::new()),
+            PortDevice::PowerPad => Box::new(PowerPad::new()),
+            PortDevice::Unplugged => Box::new(Unplugged),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cartridge::mem::Mem;
+    use crate::cartridge::rom::test::test_rom;
+    use crate::joypad::JoypadButton;
+
+    #[test]
+    fn test_player_joypad() {
+        let mut ports: Ports = [Box::new(Joypad::new()), Box::new(Vaus::new())];
+        assert!(player_joypad(&mut ports, 0).is_some());
+        assert!(player_joypad(&mut ports, 1).is_none());
+        assert!(player_joypad(&mut ports, 2).is_none());
+
+        let mut ports: Ports = [Box::new(FourScore::new(0)), Box::new(FourScore::new(1))];
+        player_joypad(&mut ports, 3)
+            .unwrap()
+            .set_button_pressed_status(JoypadButton::START, true);
+        let four_score = device_mut::<FourScore>(ports[1].as_mut()).unwrap();
+        assert_eq!(
+            four_score.pads[1].button_status.bits(),
+            JoypadButton::START.bits()
+        );
+        assert!(player_joypad(&mut ports, 4).is_none());
+    }
+
+    #[test]
+    fn test_plugged_devices() {
+        let mut bus = Bus::new(test_rom(), |_, _, _| {});
+        bus.ports[1] = PortDevice::parse("zapper").unwrap().create();
+        bus.device_mut::<Zapper>(1).unwrap().trigger = true;
+        assert_eq!(bus.mem_read(0x4017), 0x58);
+        assert!(bus.device_mut::<Joypad>(1).is_none());
+
+        // the zapper looks at the frame where the ppu is
+        bus.frame.set_pixel(10, 0, (0xff, 0xff, 0xff));
+        bus.device_mut::<Zapper>(1).unwrap().aim = Some((10, 0));
+        assert_eq!(bus.mem_read(0x4017), 0x50);
+
+        bus.ports[1] = PortDevice::parse("none").unwrap().create();
+        assert_eq!(bus.mem_read(0x4017), 0x40);
+        assert!(PortDevice::parse("mouse").is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::input::InputDevice;
+
+// the order the buttons come out on d3 and d4, 1s after them
+const D3_BUTTONS: [u8; 8] = [2, 1, 5, 9, 6, 10, 11, 7];
+const D4_BUTTONS: [u8; 4] = [4, 3, 12, 8];
+
+// https://www.nesdev.org/wiki/Power_Pad
+// a mat with 12 buttons, numbered like on side B:
+//   1  2  3  4
+//   5  6  7  8
+//   9 10 11 12
+// the strobe latches them, every read shifts one out on d3 and one on d4.
+pub struct PowerPad {
+    // bit n-1 is button n
+    pub buttons: u16,
+    strobe: bool,
+    index: usize,
+}
+
+impl Default for PowerPad {
+    fn default() -> Self {
+        PowerPad::new()
+    }
+}
+
+impl PowerPad {
+    pub fn new() -> Self {
+        PowerPad {
+            buttons: 0,
+            strobe: false,
+            index: 0,
+        }
+    }
+
+    /// `button` is 1-12.
+    pub fn set_button_pressed_status(&mut self, button: u8, pressed: bool) {
+        let mask = 1 << (button - 1);
+        if pressed {
+            self.buttons |= mask;
+        } else {
+            self.buttons &= !mask;
+        }
+    }
+
+    fn pressed(&self, buttons: &[u8]) -> u8 {
+        buttons
+            .get(self.index)
+            .map_or(1, |button| ((self.buttons >> (button - 1)) & 1) as u8)
+    }
+}
+
+impl InputDevice for PowerPad {
+    fn write(&mut self, data: u8) {
+        self.strobe = data & 1 == 1;
+        if self.strobe {
+            self.index = 0;
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        let response = self.peek();
+        if !self.strobe && self.index < D3_BUTTONS.len() {
+            self.index += 1;
+        }
+        response
+    }
+
+    fn peek(&self) -> u8 {
+        self.pressed(&D4_BUTTONS) << 4 | self.pressed(&D3_BUTTONS) << 3
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_power_pad() {
+        let mut pad = PowerPad::new();
+        pad.set_button_pressed_status(1, true);
+        pad.set_button_pressed_status(12, true);
+        pad.set_button_pressed_status(7, true);
+        pad.set_button_pressed_status(7, false);
+        pad.write(1);
+        pad.write(0);
+        let reads = (0..9).map(|_| pad.read()).collect::<Vec<u8>>();
+        // button 1 is the second d3 bit, 12 the third d4 bit
+        assert_eq!(reads[..4], [0x00, 0x08, 0x10, 0x00]);
+        assert_eq!(reads[4..8], [0x10, 0x10, 0x10, 0x10]);
+        assert_eq!(reads[8], 0x18);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::input::InputDevice;
+
+// https://www.nesdev.org/wiki/Arkanoid_controller
+// the NES Arkanoid "Vaus" paddle: a knob and a fire button. the strobe
+// latches the knob, then every read shifts out one bit of it, MSB first and
+// inverted, on d4. the button is on d3.
+pub struct Vaus {
+    // the knob, as the game has it once it undid the inversion
+    pub position: u8,
+    pub fire: bool,
+    shift: u8,
+    strobe: bool,
+}
+
+impl Default for Vaus {
+    fn default() -> Self {
+        Vaus::new()
+    }
+}
+
+impl Vaus {
+    pub fn new() -> Self {
+        Vaus {
+            position: 0x80,
+            fire: false,
+            shift: 0,
+            strobe: false,
+        }
+    }
+}
+
+impl InputDevice for Vaus {
+    fn write(&mut self, data: u8) {
+        self.strobe = data & 1 == 1;
+        if self.strobe {
+            self.shift = !self.position;
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        let response = self.peek();
+        if self.strobe {
+            self.shift = !self.position;
+        } else {
+            self.shift <<= 1;
+        }
+        response
+    }
+
+    fn peek(&self) -> u8 {
+        (self.shift >> 7) << 4 | (self.fire as u8) << 3
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vaus() {
+        let mut vaus = Vaus::new();
+        vaus.position = 0b1010_0110;
+        vaus.fire = true;
+        vaus.write(1);
+        vaus.write(0);
+        let knob = (0..8).fold(0u8, |value, _| {
+            let bits = vaus.read();
+            assert_eq!(bits & 0x08, 0x08);
+            value << 1 | (bits >> 4) & 1
+        });
+        assert_eq!(!knob, 0b1010_0110);
+        // the knob moving doesn't change a latched value
+        vaus.position = 0;
+        vaus.fire = false;
+        assert_eq!(vaus.read(), 0);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::input::InputDevice;
+use crate::rendering::frame::Frame;
+
+// the photodiode keeps seeing light for a while after the beam went by
+const SENSED_SCANLINES: u16 = 20;
+// only the white-ish targets the games flash count as light
+const BRIGHTNESS_THRESHOLD: u32 = 0xc0;
+
+// https://www.nesdev.org/wiki/Zapper
+// a light gun, no shift register: d3 is low while the sensor sees light,
+// d4 is high while the trigger is pulled.
+pub struct Zapper {
+    pub trigger: bool,
+    pub light: bool,
+    // the screen pixel the gun points at, none when it points off the screen
+    pub aim: Option<(usize, usize)>,
+}
+
+impl Default for Zapper {
+    fn default() -> Self {
+        Zapper::new()
+    }
+}
+
+impl Zapper {
+    pub fn new() -> Self {
+        Zapper {
+            trigger: false,
+            light: false,
+            aim: None,
+        }
+    }
+
+    /// whether the pixel under the aim is bright and the beam drew it just
+    /// before `scanline`.
+    pub fn senses_light(&self, frame: &Frame, scanline: u16) -> bool {
+        let Some((x, y)) = self.aim else {
+            return false;
+        };
+        let since = (scanline as usize).wrapping_sub(y);
+        if since >= SENSED_SCANLINES as usize {
+            return false;
+        }
+        let (r, g, b) = frame.get_pixel(x, y);
+        // rec. 601 luma
+        (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000 >= BRIGHTNESS_THRESHOLD
+    }
+}
+
+impl InputDevice for Zapper {
+    fn write(&mut self, _data: u8) {}
+
+    fn read(&mut self) -> u8 {
+        self.peek()
+    }
+
+    fn peek(&self) -> u8 {
+        (self.trigger as u8) << 4 | (!self.light as u8) << 3
+    }
+
+    fn observe(&mut self, frame: &Frame, scanline: u16) {
+        self.light = self.senses_light(frame, scanline);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_zapper() {
+        let mut zapper = Zapper::new();
+        assert_eq!(zapper.read(), 0x08);
+        zapper.light = true;
+        zapper.trigger = true;
+        zapper.write(1);
+        assert_eq!(zapper.read(), 0x10);
+    }
+
+    #[test]
+    fn test_zapper_light() {
+        let mut frame = Frame::new();
+        frame.set_pixel(100, 50, (0xff, 0xff, 0xff));
+        frame.set_pixel(101, 50, (0x00, 0x3a, 0xff));
+        let mut zapper = Zapper::new();
+        // pointing off the screen
+        zapper.observe(&frame, 55);
+        assert_eq!(zapper.read(), 0x08);
+
+        zapper.aim = Some((100, 50));
+        let sensed =
+            [40, 49, 50, 55, 69, 70, 261].map(|scanline| zapper.senses_light(&frame, scanline));
+        assert_eq!(sensed, [false, false, true, true, true, false, false]);
+        zapper.observe(&frame, 55);
+        assert_eq!(zapper.read(), 0x00);
+
+        // a dark blue isn't bright enough
+        zapper.aim = Some((101, 50));
+        zapper.observe(&frame, 55);
+        assert_eq!(zapper.read(), 0x08);
+    }
+}
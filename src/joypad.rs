@@ -1,5 +1,7 @@
 use bitflags::bitflags;
 
+use crate::input::InputDevice;
+
 bitflags! {
     #[derive(Debug, Clone)]
     pub struct JoypadButton: u8 {
@@ -36,16 +38,74 @@ impl Joypad {
     }
 
     pub fn read(&mut self) -> u8 {
+        let response = self.peek();
         if self.index > 7 {
-            return 1;
+            return response;
         }
-        let response = (self.button_status.bits() & (1 << self.index)) >> self.index;
         if !self.strobe && self.index <= 7 {
             self.index += 1;
         }
         response
     }
+    /// the bit the next read returns, without shifting the report.
+    pub fn peek(&self) -> u8 {
+        if self.index > 7 {
+            return 1;
+        }
+        (self.button_status.bits() & (1 << self.index)) >> self.index
+    }
     pub fn set_button_pressed_status(&mut self, button: JoypadButton, pressed: bool) {
         self.button_status.set(button, pressed);
     }
 }
+
+// https://www.nesdev.org/wiki/Standard_controller
+impl InputDevice for Joypad {
+    fn write(&mut self, data: u8) {
+        Joypad::write(self, data)
+    }
+
+    fn read(&mut self) -> u8 {
+        Joypad::read(self)
+    }
+
+    fn peek(&self) -> u8 {
+        Joypad::peek(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cartridge::mem::Mem;
+    use crate::cartridge::rom::test::test_rom;
+
+    #[test]
+    fn test_two_controller_ports() {
+        let mut bus = Bus::new(test_rom(), |_, _, _| {});
+        bus.device_mut::<Joypad>(0)
+            .unwrap()
+            .set_button_pressed_status(JoypadButton::BUTTON_A, true);
+        bus.device_mut::<Joypad>(1)
+            .unwrap()
+            .set_button_pressed_status(JoypadButton::BUTTON_B, true);
+        // the strobe reloads both shift registers
+        bus.mem_write(0x4016, 1);
+        bus.mem_write(0x4016, 0);
+        assert_eq!(bus.mem_read(0x4016), 0x41);
+        assert_eq!(bus.mem_read(0x4017), 0x40);
+        assert_eq!(bus.mem_read(0x4017), 0x41);
+        // port 1 is still on its second bit
+        assert_eq!(bus.mem_read(0x4016), 0x40);
+
+        // $4017 writes go to the apu, not to the controllers
+        bus.mem_write(0x4017, 1);
+        assert_eq!(bus.mem_read(0x4017), 0x40);
+        for _ in 0..6 {
+            bus.mem_read(0x4016);
+        }
+        // an official pad returns 1 after its 8 buttons
+        assert_eq!(bus.mem_read(0x4016), 0x41);
+    }
+}
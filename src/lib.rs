@@ -0,0 +1,15 @@
+pub mod bus;
+pub mod cartridge;
+pub mod cheats;
+pub mod cli;
+pub mod cpu_internals;
+pub mod debugger;
+pub mod input;
+pub mod joypad;
+pub mod movie;
+pub mod ppu;
+pub mod region;
+pub mod render;
+pub mod rendering;
+pub mod test_rom;
+pub mod utils;
@@ -1,31 +1,46 @@
-mod bus;
-mod cartridge;
-mod cpu_internals;
-mod joypad;
-mod ppu;
-mod render;
-mod rendering;
-mod utils;
-
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
 
-use crate::cpu_internals::cpu::CPU;
-use cartridge::mem::Mem;
-use cartridge::rom::ROM;
-use joypad::Joypad;
-use ppu::NesPPU;
-
-use render::render;
-use rendering::frame::Frame;
+use rust_nes::bus::{self, Bus};
+use rust_nes::cartridge::cdl::CodeDataLog;
+use rust_nes::cartridge::mem::Mem;
+use rust_nes::cartridge::rom::ROM;
+use rust_nes::cli;
+use rust_nes::cpu_internals::cpu::CPU;
+use rust_nes::cpu_internals::cpu_bus::CpuBus;
+use rust_nes::debugger::gdb::GdbStub;
+use rust_nes::debugger::watch_bus::WatchBus;
+use rust_nes::debugger::Debugger;
+use rust_nes::input::{
+    self, four_score::FourScore, power_pad::PowerPad, vaus::Vaus, zapper::Zapper, Ports,
+};
+use rust_nes::joypad::JoypadButton;
+use rust_nes::movie::{self, Movie, MovieSession};
+use rust_nes::ppu::NesPPU;
+use rust_nes::region::Region;
+use rust_nes::rendering::frame::Frame;
+use rust_nes::rendering::overscan::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use rust_nes::test_rom;
+use rust_nes::utils::disasm;
+use rust_nes::utils::show_nametable::NametableViewer;
+use rust_nes::utils::show_oam::OamViewer;
+use rust_nes::utils::show_tile::PatternViewer;
+use rust_nes::utils::symbols::Symbols;
+use rust_nes::utils::trace::trace_with_symbols;
 use sdl2::{
-    event::Event,
+    controller::{Button, GameController},
+    event::{Event, WindowEvent},
     keyboard::Keycode,
+    mouse::MouseButton,
     pixels::{Color, PixelFormatEnum},
     rect::Point,
     EventPump,
 };
 
-fn handle_user_input(cpu: &mut CPU, event_pump: &mut EventPump) {
+fn handle_user_input(cpu: &mut CPU<Bus>, event_pump: &mut EventPump) {
     for event in event_pump.poll_iter() {
         match event {
             Event::Quit { .. }
@@ -53,7 +68,7 @@ fn handle_user_input(cpu: &mut CPU, event_pump: &mut EventPump) {
         }
     }
 }
-fn read_screen_state(cpu: &mut CPU, frame: &mut [u8; 32 * 3 * 32]) -> bool {
+fn read_screen_state(cpu: &mut CPU<Bus>, frame: &mut [u8; 32 * 3 * 32]) -> bool {
     let mut frame_idx = 0;
     let mut update = false;
     for i in 0x0200..0x600 {
@@ -83,46 +98,310 @@ fn color(byte: u8) -> Color {
     }
 }
 
+// the right face button is A, like on the NES pad
+fn controller_button(button: Button) -> Option<JoypadButton> {
+    match button {
+        Button::DPadUp => Some(JoypadButton::UP),
+        Button::DPadDown => Some(JoypadButton::DOWN),
+        Button::DPadLeft => Some(JoypadButton::LEFT),
+        Button::DPadRight => Some(JoypadButton::RIGHT),
+        Button::Back => Some(JoypadButton::SELECT),
+        Button::Start => Some(JoypadButton::START),
+        Button::B => Some(JoypadButton::BUTTON_A),
+        Button::A => Some(JoypadButton::BUTTON_B),
+        _ => None,
+    }
+}
+
+// emulated time a test rom gets before it counts as hung
+const TEST_ROM_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn run_test_rom(rom: ROM) -> ! {
+    match test_rom::run_test_rom(rom, TEST_ROM_TIMEOUT) {
+        Ok(result) => {
+            if !result.message.is_empty() {
+                println!("{}", result.message);
+            }
+            println!("{:?}", result.status);
+            std::process::exit(if result.passed() { 0 } else { 1 });
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_disassembly(rom: &ROM, symbols: &Symbols, cdl: Option<&CodeDataLog>) -> ! {
+    let banks = rom.prg_rom.len().div_ceil(0x4000);
+    for bank in 0..banks {
+        println!("; prg bank {}", bank);
+        match disasm::disassemble_prg_bank(rom, bank, symbols, cdl) {
+            Ok(lines) => lines.iter().for_each(|line| println!("{}", line)),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    std::process::exit(0);
+}
+
+fn load_movie(path: &str, rom: &ROM) -> Movie {
+    let result = std::fs::read_to_string(path)
+        .map_err(|e| format!("can't read movie {}: {}", path, e))
+        .and_then(|text| Movie::parse(&text));
+    let movie = result.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    // fceux plays it anyway, so do we
+    if movie.header_value("romChecksum") != Some(&movie::rom_checksum(rom)) {
+        eprintln!("warning: {} was recorded with another rom", path);
+    }
+    movie
+}
+
+// a movie only stays in sync with the timing it was recorded with
+fn movie_region(movie: &Movie, requested: Option<Region>, rom: &ROM) -> Region {
+    let pal = movie.is_pal();
+    match requested {
+        Some(region) if (region == Region::PAL) != pal => {
+            eprintln!(
+                "the movie was recorded on {} but the region is {:?}",
+                if pal { "PAL" } else { "NTSC" },
+                region
+            );
+            std::process::exit(1);
+        }
+        Some(region) => region,
+        None if (rom.region == Region::PAL) == pal => rom.region,
+        None if pal => Region::PAL,
+        None => Region::NTSC,
+    }
+}
+
+// an existing log is extended, a missing one starts empty
+fn load_code_data_log(path: &str, rom: &ROM) -> CodeDataLog {
+    let result = match std::fs::read(path) {
+        Ok(bytes) => CodeDataLog::from_bytes(&bytes, rom.prg_rom.len(), rom.chr_rom.len()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Ok(CodeDataLog::new(rom.prg_rom.len(), rom.chr_rom.len()))
+        }
+        Err(e) => Err(format!("can't read code/data log {}: {}", path, e)),
+    };
+    result.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+fn open_viewer<V>(name: &str, viewer: Result<V, String>) -> V {
+    viewer.unwrap_or_else(|e| {
+        eprintln!("can't open the {} viewer: {}", name, e);
+        std::process::exit(1);
+    })
+}
+
+// line buffered: errors leave through `process::exit`, which skips destructors
+fn open_trace_output(target: &str) -> LineWriter<Box<dyn Write>> {
+    let output: Box<dyn Write> = if target == "-" {
+        Box::new(std::io::stdout())
+    } else {
+        match std::fs::File::create(target) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                eprintln!("can't create trace file {}: {}", target, e);
+                std::process::exit(1);
+            }
+        }
+    };
+    LineWriter::new(output)
+}
+
+// reset and run the cpu until the window is closed (`quit` is set),
+// the trace line of an instruction is written before `hook` sees it
+fn run<M, F>(
+    mut cpu: CPU<M>,
+    options: &cli::Options,
+    symbols: &Symbols,
+    quit: &Cell<bool>,
+    mut hook: F,
+) -> Result<CPU<M>, String>
+where
+    M: CpuBus,
+    F: FnMut(&mut CPU<M>),
+{
+    cpu.jam_policy = options.jam_policy;
+    cpu.reset();
+    let mut output = options.trace.as_deref().map(open_trace_output);
+    let mut was_jammed = false;
+    cpu.run_with_callback(|cpu| {
+        if quit.get() {
+            cpu.halted = true;
+            return;
+        }
+        // a jammed cpu is traced once, not on every frame it stays stuck
+        let traced = cpu.jammed && was_jammed;
+        was_jammed = cpu.jammed;
+        if let Some(output) = output.as_mut().filter(|_| !traced) {
+            if let Err(e) = writeln!(output, "{}", trace_with_symbols(cpu, symbols)) {
+                eprintln!("can't write the trace: {}", e);
+                std::process::exit(1);
+            }
+        }
+        hook(cpu);
+    })?;
+    Ok(cpu)
+}
+
 fn main() {
+    let options = match cli::Options::parse(std::env::args().skip(1)) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let bytes: Vec<u8> = std::fs::read(options.rom_path.as_str()).unwrap();
+    let rom = ROM::new(&bytes).unwrap();
+    let mut symbols = Symbols::new();
+    for path in &options.symbols {
+        if let Err(e) = symbols.load(Path::new(path), rom.prg_rom.len()) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    if options.test_rom {
+        run_test_rom(rom);
+    }
+    let cdl = options
+        .cdl
+        .as_deref()
+        .map(|path| load_code_data_log(path, &rom));
+    if options.disasm {
+        print_disassembly(&rom, &symbols, cdl.as_ref());
+    }
+    let movie = options.play.as_deref().map(|path| load_movie(path, &rom));
+    let region = match &movie {
+        Some(movie) => movie_region(movie, options.region, &rom),
+        None => options.region.unwrap_or(rom.region),
+    };
+    let overscan = options
+        .overscan
+        .unwrap_or_else(|| region.default_overscan());
+
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
     let window = video_subsystem
-        .window("nes game", (256.0 * 2.0) as u32, (232.0 * 2.0) as u32)
+        .window(
+            "nes game",
+            (overscan.width() * 2) as u32,
+            (overscan.height() * 2) as u32,
+        )
         .position_centered()
         .build()
         .unwrap();
 
-    let mut canvas = window.into_canvas().present_vsync().build().unwrap();
+    // no vsync, the game loop sleeps to the frame rate of the region
+    let mut canvas = window.into_canvas().build().unwrap();
+    // with a viewer open, closing the game window doesn't send a quit event
+    let game_window_id = canvas.window().id();
     let mut event_pump = sdl_context.event_pump().unwrap();
     canvas.set_scale(1.0, 1.0).unwrap();
     let creator = canvas.texture_creator();
     let mut texture = creator
-        .create_texture_target(PixelFormatEnum::RGB24, 256, 232)
+        .create_texture_target(
+            PixelFormatEnum::RGB24,
+            SCREEN_WIDTH as u32,
+            SCREEN_HEIGHT as u32,
+        )
         .unwrap();
+    let visible = overscan.rect();
+    let visible_rect = sdl2::rect::Rect::new(
+        visible.x1 as i32,
+        visible.y1 as i32,
+        overscan.width() as u32,
+        overscan.height() as u32,
+    );
 
-    let args = match std::env::args().nth(1) {
-        Some(v) => v,
-        None => String::from("nestest.nes"),
-    };
-    // show_tile_viewer(canvas, texture, event_pump, args);
-    let bytes: Vec<u8> = std::fs::read(args.as_str()).unwrap();
-    let rom = ROM::new(&bytes).unwrap();
+    let export_path = |suffix: &str| Path::new(&options.rom_path).with_extension(suffix);
+    let mut patterns = options.patterns.then(|| {
+        open_viewer(
+            "pattern table",
+            PatternViewer::new(&video_subsystem, export_path("patterns.png")),
+        )
+    });
+    let mut nametables = options.nametables.then(|| {
+        open_viewer(
+            "nametable",
+            NametableViewer::new(&video_subsystem, export_path("nametables.png")),
+        )
+    });
+    let mut oam = options.oam.then(|| {
+        open_viewer(
+            "sprite",
+            OamViewer::new(&video_subsystem, export_path("oam.png")),
+        )
+    });
 
+    // keyboard bindings of players 1 and 2, to a player and a button
     let mut key_map = HashMap::new();
-    key_map.insert(Keycode::W, joypad::JoypadButton::UP);
-    key_map.insert(Keycode::A, joypad::JoypadButton::LEFT);
-    key_map.insert(Keycode::S, joypad::JoypadButton::DOWN);
-    key_map.insert(Keycode::D, joypad::JoypadButton::RIGHT);
-    key_map.insert(Keycode::Space, joypad::JoypadButton::SELECT);
-    key_map.insert(Keycode::Return, joypad::JoypadButton::START);
-    key_map.insert(Keycode::H, joypad::JoypadButton::BUTTON_A);
-    key_map.insert(Keycode::J, joypad::JoypadButton::BUTTON_B);
-
-    let bus = bus::Bus::new(
+    key_map.insert(Keycode::W, (0, JoypadButton::UP));
+    key_map.insert(Keycode::A, (0, JoypadButton::LEFT));
+    key_map.insert(Keycode::S, (0, JoypadButton::DOWN));
+    key_map.insert(Keycode::D, (0, JoypadButton::RIGHT));
+    key_map.insert(Keycode::Space, (0, JoypadButton::SELECT));
+    key_map.insert(Keycode::Return, (0, JoypadButton::START));
+    key_map.insert(Keycode::H, (0, JoypadButton::BUTTON_A));
+    key_map.insert(Keycode::J, (0, JoypadButton::BUTTON_B));
+    key_map.insert(Keycode::Up, (1, JoypadButton::UP));
+    key_map.insert(Keycode::Left, (1, JoypadButton::LEFT));
+    key_map.insert(Keycode::Down, (1, JoypadButton::DOWN));
+    key_map.insert(Keycode::Right, (1, JoypadButton::RIGHT));
+    key_map.insert(Keycode::RShift, (1, JoypadButton::SELECT));
+    key_map.insert(Keycode::RCtrl, (1, JoypadButton::START));
+    key_map.insert(Keycode::Period, (1, JoypadButton::BUTTON_A));
+    key_map.insert(Keycode::Comma, (1, JoypadButton::BUTTON_B));
+    // the number row, 1 to =, is the Power Pad's buttons 1 to 12
+    let power_pad_keys = [
+        Keycode::Num1,
+        Keycode::Num2,
+        Keycode::Num3,
+        Keycode::Num4,
+        Keycode::Num5,
+        Keycode::Num6,
+        Keycode::Num7,
+        Keycode::Num8,
+        Keycode::Num9,
+        Keycode::Num0,
+        Keycode::Minus,
+        Keycode::Equals,
+    ];
+    let window_width = overscan.width() * 2;
+
+    // game controllers are players 1 to 4 in the order they are plugged in
+    let controller_subsystem = sdl_context.game_controller().unwrap();
+    let mut controllers: [Option<GameController>; 4] = [None, None, None, None];
+
+    // set by the window, the cpu stops before the next instruction
+    let quit = &Cell::new(false);
+    let mut last_frame = Instant::now();
+    let mut bus = bus::Bus::new(
         rom,
-        move |ppu: &NesPPU, joypad: &mut Joypad, frame: &Frame| {
+        move |ppu: &NesPPU, ports: &mut Ports, frame: &Frame| {
             texture.update(None, &frame.data, 256 * 2 * 3).unwrap();
-            canvas.copy(&texture, None, None).unwrap();
+            canvas.copy(&texture, Some(visible_rect), None).unwrap();
+            if let Some(sprite) = oam.as_ref().and_then(|viewer| viewer.highlight(ppu)) {
+                // the window shows the visible area twice as big
+                let x = (sprite.x1 as i32 - visible_rect.x()) * 2;
+                let y = (sprite.y1 as i32 - visible_rect.y()) * 2;
+                let (width, height) = ((sprite.x2 - sprite.x1) * 2, (sprite.y2 - sprite.y1) * 2);
+                canvas.set_draw_color(Color::MAGENTA);
+                canvas
+                    .draw_rect(sdl2::rect::Rect::new(x, y, width as u32, height as u32))
+                    .unwrap();
+            }
 
             // // draw grid lines
             // let tmp = canvas.draw_color();
@@ -139,24 +418,128 @@ fn main() {
             // }
             //
             canvas.present();
+            if let Some(Err(e)) = patterns.as_mut().map(|viewer| viewer.update(ppu)) {
+                eprintln!("{}", e);
+            }
+            if let Some(Err(e)) = nametables.as_mut().map(|viewer| viewer.update(ppu)) {
+                eprintln!("{}", e);
+            }
+            if let Some(Err(e)) = oam.as_mut().map(|viewer| viewer.update(ppu)) {
+                eprintln!("{}", e);
+            }
+
+            // keep 60fps on NTSC and 50fps on PAL/Dendy
+            let elapsed = last_frame.elapsed();
+            let frame_duration = ppu.region.frame_duration();
+            if elapsed < frame_duration {
+                std::thread::sleep(frame_duration - elapsed);
+            }
+            last_frame = Instant::now();
 
             for event in event_pump.poll_iter() {
+                if patterns
+                    .as_mut()
+                    .is_some_and(|viewer| viewer.handle_event(&event))
+                    || nametables
+                        .as_mut()
+                        .is_some_and(|viewer| viewer.handle_event(&event))
+                    || oam
+                        .as_mut()
+                        .is_some_and(|viewer| viewer.handle_event(&event))
+                {
+                    continue;
+                }
                 match event {
                     Event::Quit { .. }
                     | Event::KeyDown {
                         keycode: Some(Keycode::Escape),
                         ..
-                    } => std::process::exit(0),
-                    Event::KeyDown { keycode, .. } => {
-                        // println!("DOWN {:?}", keycode);
-                        if let Some(key) = key_map.get(&keycode.unwrap_or(Keycode::Ampersand)) {
-                            joypad.set_button_pressed_status(key.clone(), true);
+                    } => quit.set(true),
+                    Event::Window {
+                        win_event: WindowEvent::Close,
+                        window_id,
+                        ..
+                    } if window_id == game_window_id => quit.set(true),
+                    Event::KeyDown {
+                        keycode: Some(keycode),
+                        ..
+                    }
+                    | Event::KeyUp {
+                        keycode: Some(keycode),
+                        ..
+                    } => {
+                        let pressed = matches!(event, Event::KeyDown { .. });
+                        if let Some((player, button)) = key_map.get(&keycode) {
+                            if let Some(joypad) = input::player_joypad(ports, *player) {
+                                joypad.set_button_pressed_status(button.clone(), pressed);
+                            }
+                        }
+                        let button = power_pad_keys.iter().position(|key| *key == keycode);
+                        let power_pad = input::device_mut::<PowerPad>(ports[1].as_mut());
+                        if let (Some(button), Some(power_pad)) = (button, power_pad) {
+                            power_pad.set_button_pressed_status(button as u8 + 1, pressed);
+                        }
+                    }
+                    // the Vaus knob follows the mouse across the window, the
+                    // Zapper aims at the pixel under it
+                    Event::MouseMotion { x, y, .. } => {
+                        if let Some(vaus) = input::device_mut::<Vaus>(ports[1].as_mut()) {
+                            vaus.position = (x.max(0) as usize * 256 / window_width).min(255) as u8;
+                        }
+                        if let Some(zapper) = input::device_mut::<Zapper>(ports[1].as_mut()) {
+                            let x = (x.max(0) / 2 + visible_rect.x()) as usize;
+                            let y = (y.max(0) / 2 + visible_rect.y()) as usize;
+                            zapper.aim = Some((x.min(SCREEN_WIDTH - 1), y.min(SCREEN_HEIGHT - 1)));
                         }
                     }
-                    Event::KeyUp { keycode, .. } => {
-                        // println!("UP {:?}", keycode);
-                        if let Some(key) = key_map.get(&keycode.unwrap_or(Keycode::Ampersand)) {
-                            joypad.set_button_pressed_status(key.clone(), false);
+                    Event::Window {
+                        win_event: WindowEvent::Leave,
+                        ..
+                    } => {
+                        if let Some(zapper) = input::device_mut::<Zapper>(ports[1].as_mut()) {
+                            zapper.aim = None;
+                        }
+                    }
+                    Event::MouseButtonDown {
+                        mouse_btn: MouseButton::Left,
+                        ..
+                    }
+                    | Event::MouseButtonUp {
+                        mouse_btn: MouseButton::Left,
+                        ..
+                    } => {
+                        let pressed = matches!(event, Event::MouseButtonDown { .. });
+                        if let Some(vaus) = input::device_mut::<Vaus>(ports[1].as_mut()) {
+                            vaus.fire = pressed;
+                        }
+                        if let Some(zapper) = input::device_mut::<Zapper>(ports[1].as_mut()) {
+                            zapper.trigger = pressed;
+                        }
+                    }
+                    Event::ControllerDeviceAdded { which, .. } => {
+                        if let Some(slot) = controllers.iter_mut().find(|slot| slot.is_none()) {
+                            match controller_subsystem.open(which) {
+                                Ok(controller) => *slot = Some(controller),
+                                Err(e) => eprintln!("can't open controller {}: {}", which, e),
+                            }
+                        }
+                    }
+                    Event::ControllerDeviceRemoved { which, .. } => {
+                        for slot in controllers.iter_mut() {
+                            if slot.as_ref().is_some_and(|c| c.instance_id() == which) {
+                                *slot = None;
+                            }
+                        }
+                    }
+                    Event::ControllerButtonDown { which, button, .. }
+                    | Event::ControllerButtonUp { which, button, .. } => {
+                        let pressed = matches!(event, Event::ControllerButtonDown { .. });
+                        let player = controllers.iter().position(|slot| {
+                            slot.as_ref().is_some_and(|c| c.instance_id() == which)
+                        });
+                        let joypad = player.and_then(|player| input::player_joypad(ports, player));
+                        if let (Some(joypad), Some(button)) = (joypad, controller_button(button)) {
+                            joypad.set_button_pressed_status(button, pressed);
                         }
                     }
                     _ => {}
@@ -164,8 +547,73 @@ fn main() {
             }
         },
     );
+    bus.set_region(region);
+    if options.four_score {
+        bus.ports = [Box::new(FourScore::new(0)), Box::new(FourScore::new(1))];
+    } else {
+        bus.ports[1] = options.port2.create();
+    }
+    if let Some(cdl) = cdl {
+        bus.start_code_data_log(cdl);
+    }
+    for cheat in &options.cheats {
+        bus.cheats.add(*cheat);
+    }
+    if let Some(movie) = movie {
+        bus.movie = Some(MovieSession::play(movie));
+    } else if options.record.is_some() {
+        let name = Path::new(&options.rom_path)
+            .file_stem()
+            .map_or(String::new(), |stem| stem.to_string_lossy().to_string());
+        let movie = Movie::new(&bus.rom, &name, bus.region());
+        bus.movie = Some(MovieSession::record(movie));
+    }
 
-    let mut cpu = CPU::new(bus);
-    cpu.reset();
-    cpu.run();
+    let result = if let Some(port) = options.gdb_port {
+        let mut stub = match GdbStub::listen(("127.0.0.1", port)) {
+            Ok(stub) => stub,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        println!("waiting for gdb on 127.0.0.1:{}", port);
+        run(
+            CPU::new(WatchBus::new(bus)),
+            &options,
+            &symbols,
+            quit,
+            |cpu| stub.on_instruction(cpu),
+        )
+        .map(|cpu| cpu.bus.inner)
+    } else if options.debug {
+        let mut debugger = Debugger::new();
+        debugger.symbols = symbols.clone();
+        run(
+            CPU::new(WatchBus::new(bus)),
+            &options,
+            &symbols,
+            quit,
+            |cpu| debugger.on_instruction(cpu),
+        )
+        .map(|cpu| cpu.bus.inner)
+    } else {
+        run(CPU::new(bus), &options, &symbols, quit, |_| {}).map(|cpu| cpu.bus)
+    };
+    let bus = result.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    if let (Some(path), Some(cdl)) = (options.cdl.as_deref(), bus.code_data_log()) {
+        if let Err(e) = std::fs::write(path, cdl.to_bytes()) {
+            eprintln!("can't write code/data log {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+    if let (Some(path), Some(session)) = (options.record.as_deref(), bus.movie.as_ref()) {
+        if let Err(e) = std::fs::write(path, session.movie.to_fm2()) {
+            eprintln!("can't write movie {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,382 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cartridge::rom::ROM;
+use crate::joypad::{Joypad, JoypadButton};
+use crate::region::Region;
+
+// the gamepad column of an input line, a letter when pressed
+const BUTTONS: [(char, JoypadButton); 8] = [
+    ('R', JoypadButton::RIGHT),
+    ('L', JoypadButton::LEFT),
+    ('D', JoypadButton::DOWN),
+    ('U', JoypadButton::UP),
+    ('T', JoypadButton::START),
+    ('S', JoypadButton::SELECT),
+    ('B', JoypadButton::BUTTON_B),
+    ('A', JoypadButton::BUTTON_A),
+];
+
+// the FCEUX version written to new movies, 2.2.2
+const EMU_VERSION: &str = "22020";
+
+// an FCEUX text movie: a header of `key value` lines and the gamepad of
+// port 0 for every frame from power-on.
+// https://fceux.com/web/help/fm2.html
+#[derive(Debug, Clone)]
+pub struct Movie {
+    pub header: Vec<(String, String)>,
+    pub frames: Vec<JoypadButton>,
+}
+
+impl Movie {
+    /// an empty movie to record `rom` from power-on.
+    pub fn new(rom: &ROM, rom_filename: &str, region: Region) -> Movie {
+        let pal = if region == Region::PAL { "1" } else { "0" };
+        let header = [
+            ("version", "3"),
+            ("emuVersion", EMU_VERSION),
+            ("rerecordCount", "0"),
+            ("palFlag", pal),
+            ("romFilename", rom_filename),
+            ("romChecksum", &rom_checksum(rom)),
+            ("guid", &new_guid()),
+            ("fourscore", "0"),
+            ("microphone", "0"),
+            ("port0", "1"),
+            ("port1", "0"),
+            ("port2", "0"),
+            ("FDS", "0"),
+            ("NewPPU", "0"),
+        ];
+        Movie {
+            header: header
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            frames: vec![],
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Movie, String> {
+        let mut movie = Movie {
+            header: vec![],
+            frames: vec![],
+        };
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('|') {
+                let frame = movie.frames.len();
+                let buttons =
+                    parse_input(line, frame).map_err(|e| format!("line {}: {}", number + 1, e))?;
+                movie.frames.push(buttons);
+                continue;
+            }
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            movie.header.push((key.to_string(), value.to_string()));
+        }
+        if movie.header_value("binary") == Some("1") {
+            return Err("binary fm2 movies are not supported".to_string());
+        }
+        if movie.header_value("fourscore").is_some_and(|v| v != "0")
+            || movie.header_value("port0").is_some_and(|v| v != "1")
+        {
+            return Err("only movies with a gamepad on port 0 are supported".to_string());
+        }
+        Ok(movie)
+    }
+
+    pub fn header_value(&self, key: &str) -> Option<&str> {
+        self.header
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// recorded on a PAL console (`palFlag 1`), otherwise NTSC timing.
+    pub fn is_pal(&self) -> bool {
+        self.header_value("palFlag") == Some("1")
+    }
+
+    pub fn to_fm2(&self) -> String {
+        let mut text = String::new();
+        for (key, value) in &self.header {
+            text.push_str(&format!("{} {}\n", key, value));
+        }
+        for buttons in &self.frames {
+            let pad = BUTTONS
+                .iter()
+                .map(|(letter, button)| {
+                    if buttons.contains(button.clone()) {
+                        *letter
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            text.push_str(&format!("|0|{}|||\n", pad));
+        }
+        text
+    }
+}
+
+// `|commands|RLDUTSBA|port1|port2|`, `.` or a space for a released button
+fn parse_input(line: &str, frame: usize) -> Result<JoypadButton, String> {
+    let fields = line.split('|').collect::<Vec<&str>>();
+    if fields.len() < 3 {
+        return Err(format!("invalid input {}", line));
+    }
+    let commands = fields[1]
+        .parse::<u8>()
+        .map_err(|e| format!("invalid commands {}: {}", fields[1], e))?;
+    // the run starts from power-on anyway, resets later on need the cpu
+    if commands != 0 && frame != 0 {
+        return Err(format!("reset at frame {} is not supported", frame));
+    }
+    let pad = fields[2].chars().collect::<Vec<char>>();
+    if pad.len() != BUTTONS.len() {
+        return Err(format!("invalid gamepad {}", fields[2]));
+    }
+    let mut buttons = JoypadButton::empty();
+    for (c, (_, button)) in pad.iter().zip(BUTTONS.iter()) {
+        if *c != '.' && *c != ' ' {
+            buttons.insert(button.clone());
+        }
+    }
+    Ok(buttons)
+}
+
+/// `base64:` and the MD5 of the PRG and CHR ROM, how FCEUX tells games apart.
+pub fn rom_checksum(rom: &ROM) -> String {
+    let mut data = rom.prg_rom.clone();
+    data.extend(&rom.chr_rom);
+    format!("base64:{}", base64(&md5(&data)))
+}
+
+// a random enough id from the clock, `452DE2C3-EF43-2FA9-77AC-0677FC51543B`
+fn new_guid() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos());
+    let hex = md5(&nanos.to_le_bytes())
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<String>();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn md5(data: &[u8]) -> [u8; 16] {
+    const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+    let constants = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4294967296.0) as u32)
+        .collect::<Vec<u32>>();
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend((data.len() as u64 * 8).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in message.chunks(64) {
+        let words = chunk
+            .chunks(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect::<Vec<u32>>();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), 7 * i % 16),
+            };
+            let shift = SHIFTS[i / 16 * 4 + i % 4];
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(constants[i])
+                .wrapping_add(words[g])
+                .rotate_left(shift);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+    let mut digest = [0; 16];
+    for (i, value) in state.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&value.to_le_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(bits >> (18 - i * 6)) as usize & 0x3f] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MovieMode {
+    Record,
+    Play,
+}
+
+// a movie hooked to the bus, stepped once per frame after the game loop
+// callback: recording takes the joypad as the callback left it, playback
+// overrides it.
+#[derive(Debug)]
+pub struct MovieSession {
+    pub movie: Movie,
+    pub mode: MovieMode,
+    pub frame: usize,
+}
+
+impl MovieSession {
+    pub fn record(movie: Movie) -> Self {
+        MovieSession {
+            movie,
+            mode: MovieMode::Record,
+            frame: 0,
+        }
+    }
+
+    pub fn play(movie: Movie) -> Self {
+        MovieSession {
+            movie,
+            mode: MovieMode::Play,
+            frame: 0,
+        }
+    }
+
+    pub fn next_frame(&mut self, joypad: &mut Joypad) {
+        match self.mode {
+            MovieMode::Record => self.movie.frames.push(joypad.button_status.clone()),
+            MovieMode::Play => {
+                // the joypad is left alone once the movie is over
+                if let Some(buttons) = self.movie.frames.get(self.frame) {
+                    for (_, button) in BUTTONS.iter() {
+                        let pressed = buttons.contains(button.clone());
+                        joypad.set_button_pressed_status(button.clone(), pressed);
+                    }
+                }
+            }
+        }
+        self.frame += 1;
+    }
+
+    /// playback went past the last frame.
+    pub fn finished(&self) -> bool {
+        self.mode == MovieMode::Play && self.frame >= self.movie.frames.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cartridge::rom::test::test_rom;
+
+    #[test]
+    fn test_md5_and_base64() {
+        let hex = |digest: [u8; 16]| {
+            digest
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>()
+        };
+        assert_eq!(hex(md5(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex(md5(b"abc")), "900150983cd24fb0d6963f7d28e17f72");
+        // two blocks
+        assert_eq!(
+            hex(md5("1234567890".repeat(8).as_bytes())),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(new_guid().len(), 36);
+    }
+
+    #[test]
+    fn test_fm2() {
+        let rom = test_rom();
+        let mut movie = Movie::new(&rom, "game", Region::NTSC);
+        movie.frames.push(JoypadButton::empty());
+        movie
+            .frames
+            .push(JoypadButton::RIGHT | JoypadButton::BUTTON_A);
+        let text = movie.to_fm2();
+        assert!(text.starts_with("version 3\nemuVersion 22020\n"));
+        assert!(text.contains("\nromFilename game\n"));
+        assert!(!movie.is_pal());
+        assert!(Movie::new(&rom, "game", Region::PAL).is_pal());
+        assert!(text.ends_with("|0|........|||\n|0|R......A|||\n"));
+
+        let parsed = Movie::parse(&text).unwrap();
+        assert_eq!(parsed.header, movie.header);
+        assert_eq!(parsed.frames.len(), 2);
+        assert_eq!(parsed.frames[1].bits(), 0b1000_0001);
+        assert_eq!(
+            parsed.header_value("romChecksum"),
+            Some(&*rom_checksum(&rom))
+        );
+
+        // fceux leaves spaces for released buttons in older movies
+        let old = Movie::parse("version 3\r\n|1|  D T   |||\r\n").unwrap();
+        assert_eq!(
+            old.frames[0].bits(),
+            (JoypadButton::DOWN | JoypadButton::START).bits()
+        );
+        assert!(Movie::parse("|0|........|||\n|2|........|||\n").is_err());
+        assert!(Movie::parse("|0|....|||\n").is_err());
+        assert!(Movie::parse("fourscore 1\n").is_err());
+    }
+
+    #[test]
+    fn test_movie_session() {
+        let mut joypad = Joypad::new();
+        let rom = test_rom();
+        let mut recording = MovieSession::record(Movie::new(&rom, "game", Region::NTSC));
+        joypad.set_button_pressed_status(JoypadButton::UP, true);
+        recording.next_frame(&mut joypad);
+        joypad.set_button_pressed_status(JoypadButton::UP, false);
+        recording.next_frame(&mut joypad);
+
+        let mut playback = MovieSession::play(recording.movie);
+        let mut joypad = Joypad::new();
+        playback.next_frame(&mut joypad);
+        assert_eq!(joypad.button_status.bits(), JoypadButton::UP.bits());
+        assert!(!playback.finished());
+        playback.next_frame(&mut joypad);
+        assert_eq!(joypad.button_status.bits(), 0);
+        assert!(playback.finished());
+    }
+}
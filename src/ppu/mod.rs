@@ -1,11 +1,15 @@
+pub mod open_bus;
 pub mod register;
 
+use open_bus::OpenBus;
+use std::cell::RefCell;
+
 use register::{
     addr_register::AddrRegister, control_register::ControlRegister, mask_register::MaskRegister,
     scroll_register::ScrollRegister, status_register::StatusRegister,
 };
 
-use crate::{render, rendering::frame::Frame};
+use crate::{cartridge::cdl, region::Region, render, rendering::frame::Frame};
 #[derive(Debug)]
 pub struct NesPPU {
     // visiual of a game stored
@@ -20,6 +24,7 @@ pub struct NesPPU {
     pub oam_addr: u8,
 
     pub mirroring: PPUMirroring,
+    pub region: Region,
     pub control_reg: ControlRegister,
     pub mask_reg: MaskRegister,
     pub status_reg: StatusRegister,
@@ -29,7 +34,14 @@ pub struct NesPPU {
     pub cycles: usize,
     pub scanlines: u16,
     internal_data_buf: u8,
+    // I/O data latch returned by reads of write-only registers
+    pub open_bus: OpenBus,
     pub nmi_interrupt: Option<u8>,
+    // frames since power on, counted at the start of each vblank
+    pub frame_count: u64,
+    // code/data log flags of chr_rom, `None` while not logging.
+    // in a cell as the renderer only borrows the ppu
+    pub chr_log: RefCell<Option<Vec<u8>>>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -53,7 +65,8 @@ pub trait PPU {
 
     fn read_data(&mut self) -> u8;
     fn read_status(&mut self) -> u8;
-    fn read_oam_data(&self) -> u8;
+    fn read_oam_data(&mut self) -> u8;
+    fn read_open_bus(&self) -> u8;
 
     fn write_to_oam_dma(&mut self, value: &[u8; 256]);
 }
@@ -63,6 +76,7 @@ impl NesPPU {
         NesPPU {
             chr_rom,
             mirroring,
+            region: Region::NTSC,
             palette_table: [0; 32],
             vram: [0; 2048],
             oam_data: [0; 64 * 4],
@@ -75,7 +89,10 @@ impl NesPPU {
             scanlines: 0,
             cycles: 0,
             internal_data_buf: 0,
+            open_bus: OpenBus::new(),
             nmi_interrupt: None,
+            frame_count: 0,
+            chr_log: RefCell::new(None),
         }
     }
     fn increment_vram_addr(&mut self) {
@@ -93,6 +110,8 @@ impl NesPPU {
     // if scanaline over vblank, then start vblank interrupt
     /// return is reset scanlines
     pub fn tick(&mut self, cycles: u8, frame: &mut Frame) {
+        let vblank_start = self.region.vblank_start_scanline();
+        let max_scanline = self.region.scanlines_per_frame();
         self.cycles += cycles as usize;
         // 341 ppu cycles per scan line.
         if self.cycles >= PPU_CYCLE_PER_SCAN_LINE {
@@ -105,16 +124,18 @@ impl NesPPU {
                 render::render(self, frame, self.scanlines);
             }
 
-            if self.scanlines == PPU_START_VBLANK {
+            if self.scanlines == vblank_start {
+                self.frame_count += 1;
                 self.status_reg.update_vertical_blank_started(true);
                 self.status_reg.update_sprite_0_hit(false);
                 if self.control_reg.is_generate_vblank_nmi_on() {
                     self.nmi_interrupt = Some(1);
                 }
             }
-            if self.scanlines >= PPU_MAX_SCANLINE {
+            if self.scanlines >= max_scanline {
                 self.scanlines = 0;
                 self.nmi_interrupt = None;
+                self.open_bus.tick_frame();
                 self.status_reg.update_sprite_0_hit(false);
                 self.status_reg.reset_vblank_status();
             }
@@ -122,11 +143,19 @@ impl NesPPU {
     }
 }
 const PPU_CYCLE_PER_SCAN_LINE: usize = 341;
-const PPU_START_VBLANK: u16 = 241;
-const PPU_MAX_SCANLINE: u16 = 262;
+
+fn palette_index(addr: u16) -> usize {
+    let mut index = addr & 0x1f;
+    //Addresses $3F10/$3F14/$3F18/$3F1C are mirrors of $3F00/$3F04/$3F08/$3F0C
+    if index & 0x13 == 0x10 {
+        index -= 0x10;
+    }
+    index as usize
+}
 
 impl PPU for NesPPU {
     fn write_to_control_reg(&mut self, value: u8) {
+        self.open_bus.set(value, 0xff);
         // 1 or 32
         let before_nmi_status = self.control_reg.is_generate_vblank_nmi_on();
         self.control_reg.update(value);
@@ -140,9 +169,11 @@ impl PPU for NesPPU {
     }
 
     fn write_to_ppu_addr(&mut self, value: u8) {
+        self.open_bus.set(value, 0xff);
         self.addr_reg.update(value);
     }
     fn write_to_data(&mut self, value: u8) {
+        self.open_bus.set(value, 0xff);
         let addr = self.addr_reg.get_addr();
         match addr {
             0..=0x1fff => {
@@ -152,7 +183,7 @@ impl PPU for NesPPU {
                 self.vram[self.get_mirror_vram_addr(addr) as usize] = value;
             }
             0x3000..=0x3eff => {
-                println!(
+                eprintln!(
                     "addr space 0x3000..0x3eff is not expected to be used, requested = {:x}",
                     addr
                 )
@@ -171,7 +202,7 @@ impl PPU for NesPPU {
     }
 
     fn write_to_oam_data(&mut self, value: u8) {
-        println!("oam {:08b} {:x}", value, self.oam_addr);
+        self.open_bus.set(value, 0xff);
         self.oam_data[self.oam_addr as usize] = value;
         self.oam_addr = self.oam_addr.wrapping_add(1);
     }
@@ -179,55 +210,58 @@ impl PPU for NesPPU {
     fn read_data(&mut self) -> u8 {
         let addr = self.addr_reg.get_addr();
         self.increment_vram_addr();
-        match addr {
-            0..=0x1fff => {
-                let result = self.internal_data_buf;
-                self.internal_data_buf = self.chr_rom[addr as usize];
-                result
-            }
-            0x2000..=0x2fff => {
-                let result = self.internal_data_buf;
-                self.internal_data_buf = self.vram[self.get_mirror_vram_addr(addr) as usize];
-                result
-            }
-            0x3000..=0x3eff => panic!(
-                "addr space 0x3000..0x3eff is not expected to be used, requested = {}",
-                addr
-            ),
-            //Addresses $3F10/$3F14/$3F18/$3F1C are mirrors of $3F00/$3F04/$3F08/$3F0C
-            0x3f10 | 0x3f14 | 0x3f18 | 0x3f1c => {
-                let add_mirror = addr - 0x10;
-                self.palette_table[(add_mirror - 0x3f00) as usize]
-            }
-            0x3f00..=0x3fff => self.palette_table[(addr - 0x3f00) as usize],
-            _ => panic!("unecpected access to mirrored space {}", addr),
+        let data = self.peek_memory(addr);
+        if let (0..=0x1fff, Some(log)) = (addr, self.chr_log.get_mut()) {
+            cdl::mark_chr(log, addr as usize, 1, cdl::READ);
+        }
+        if addr >= 0x3f00 {
+            // palette entries are 6 bits wide, the top 2 bits come from the latch
+            self.open_bus.set(data, 0x3f);
+        } else {
+            // everything below the palette goes through the read buffer
+            let value = self.internal_data_buf;
+            self.internal_data_buf = data;
+            self.open_bus.set(value, 0xff);
         }
+        self.open_bus.get()
     }
 
     fn read_status(&mut self) -> u8 {
-        let value = self.status_reg.snapshot();
+        let value = self.peek(0x2002);
+        self.open_bus
+            .set(value, !StatusRegister::PPU_OPEN_BUS.bits());
         self.status_reg.reset_vblank_status();
         self.addr_reg.reset_latch();
         self.scroll_register.reset_latch();
         value
     }
 
-    fn read_oam_data(&self) -> u8 {
-        self.oam_data[self.oam_addr as usize]
+    fn read_oam_data(&mut self) -> u8 {
+        let value = self.peek(0x2004);
+        self.open_bus.set(value, 0xff);
+        value
+    }
+
+    fn read_open_bus(&self) -> u8 {
+        self.open_bus.get()
     }
 
     fn write_to_mask_reg(&mut self, value: u8) {
+        self.open_bus.set(value, 0xff);
         self.mask_reg.update(value);
     }
+    // $2002 is read only, a write only fills the I/O latch.
     fn write_to_status_reg(&mut self, value: u8) {
-        self.status_reg.update(value);
+        self.open_bus.set(value, 0xff);
     }
 
     fn write_to_oam_addr(&mut self, value: u8) {
+        self.open_bus.set(value, 0xff);
         self.oam_addr = value;
     }
 
     fn write_to_scroll_reg(&mut self, value: u8) {
+        self.open_bus.set(value, 0xff);
         self.scroll_register.write(value);
     }
 
@@ -242,6 +276,54 @@ impl PPU for NesPPU {
 }
 
 impl NesPPU {
+    /// what a cpu read of the register at `addr` ($2000-$2007, mirrors included)
+    /// returns, without clearing vblank, the latches or moving the vram address.
+    pub fn peek(&self, addr: u16) -> u8 {
+        match addr & 0x2007 {
+            0x2002 => {
+                // only the top 3 bits are driven, the rest is open bus
+                (self.status_reg.snapshot() & !StatusRegister::PPU_OPEN_BUS.bits())
+                    | (self.open_bus.get() & StatusRegister::PPU_OPEN_BUS.bits())
+            }
+            0x2004 => self.oam_data[self.oam_addr as usize],
+            0x2007 => {
+                let addr = self.addr_reg.get_addr();
+                if addr >= 0x3f00 {
+                    (self.peek_memory(addr) & 0x3f) | (self.open_bus.get() & !0x3f)
+                } else {
+                    self.internal_data_buf
+                }
+            }
+            _ => self.open_bus.get(),
+        }
+    }
+
+    /// byte at `addr` in the ppu address space ($0000-$3FFF), skipping the read buffer.
+    pub fn peek_memory(&self, addr: u16) -> u8 {
+        let addr = addr & 0x3fff;
+        match addr {
+            0..=0x1fff => self.chr_rom.get(addr as usize).copied().unwrap_or(0),
+            0x2000..=0x3eff => self.vram[self.get_mirror_vram_addr(addr) as usize],
+            _ => self.palette_table[palette_index(addr)],
+        }
+    }
+
+    /// store `data` at `addr` of the ppu address space, CHR ROM included.
+    pub fn poke_memory(&mut self, addr: u16, data: u8) {
+        let addr = addr & 0x3fff;
+        let byte = match addr {
+            0..=0x1fff => self.chr_rom.get_mut(addr as usize),
+            0x2000..=0x3eff => {
+                let index = self.get_mirror_vram_addr(addr) as usize;
+                self.vram.get_mut(index)
+            }
+            _ => self.palette_table.get_mut(palette_index(addr)),
+        };
+        if let Some(byte) = byte {
+            *byte = data;
+        }
+    }
+
     // 2000  2400
     // [ A ] [ B ]
     // 2800  2C00
@@ -286,4 +368,72 @@ pub mod test {
         ppu.write_to_data(0x66);
         assert_eq!(ppu.vram[0x0305], 0x66);
     }
+
+    #[test]
+    fn test_ppu_pal_frame_has_312_scanlines() {
+        let mut ppu = NesPPU::new_empty_rom();
+        let mut frame = Frame::new();
+        ppu.region = Region::PAL;
+        ppu.control_reg.update(0x80);
+        while ppu.nmi_interrupt.is_none() {
+            ppu.tick(31, &mut frame);
+        }
+        assert_eq!(ppu.scanlines, 241);
+        // 341 = 31 * 11, so every scanline boundary is hit exactly
+        let mut ticks = 0;
+        while ppu.scanlines != 0 {
+            ppu.tick(31, &mut frame);
+            ticks += 1;
+        }
+        assert_eq!(ticks, (312 - 241) * 11);
+    }
+
+    #[test]
+    fn test_ppu_status_low_bits_come_from_open_bus() {
+        let mut ppu = NesPPU::new_empty_rom();
+        ppu.status_reg.update_vertical_blank_started(true);
+        ppu.write_to_oam_addr(0x1b);
+        assert_eq!(ppu.read_status(), 0x9b);
+        // vblank is cleared, the latch keeps the value put on the bus
+        assert_eq!(ppu.read_status(), 0x1b);
+        assert_eq!(ppu.read_open_bus(), 0x1b);
+    }
+
+    #[test]
+    fn test_ppu_palette_read_keeps_open_bus_top_bits() {
+        let mut ppu = NesPPU::new_empty_rom();
+        ppu.write_to_ppu_addr(0x3f);
+        ppu.write_to_ppu_addr(0x01);
+        ppu.write_to_data(0x2a);
+        ppu.write_to_ppu_addr(0x3f);
+        ppu.write_to_ppu_addr(0xc1);
+        assert_eq!(ppu.read_data(), 0xc0 | 0x2a);
+    }
+
+    #[test]
+    fn test_ppu_peek_has_no_side_effects() {
+        let mut ppu = NesPPU::new_empty_rom();
+        ppu.status_reg.update_vertical_blank_started(true);
+        ppu.write_to_ppu_addr(0x23);
+        ppu.write_to_ppu_addr(0x05);
+        ppu.write_to_data(0x66);
+        ppu.write_to_ppu_addr(0x23);
+        ppu.write_to_ppu_addr(0x05);
+        ppu.read_data();
+
+        assert_eq!(ppu.peek(0x2002) & 0x80, 0x80);
+        assert_eq!(ppu.peek(0x200a) & 0x80, 0x80);
+        assert!(ppu.status_reg.is_in_vblank());
+        // the buffer filled by the dummy read, the address stays put
+        assert_eq!(ppu.peek(0x2007), 0x66);
+        assert_eq!(ppu.peek(0x2007), 0x66);
+        assert_eq!(ppu.addr_reg.get_addr(), 0x2306);
+        assert_eq!(ppu.peek_memory(0x2305), 0x66);
+        // $3000-$3EFF mirrors the nametables
+        assert_eq!(ppu.peek_memory(0x3305), 0x66);
+
+        ppu.palette_table[0] = 0x0f;
+        assert_eq!(ppu.peek_memory(0x3f10), 0x0f);
+        assert_eq!(ppu.peek_memory(0x3f30), 0x0f);
+    }
 }
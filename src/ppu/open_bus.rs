@@ -0,0 +1,69 @@
+// https://www.nesdev.org/wiki/Open_bus_behavior#PPU_open_bus
+// the PPU keeps the last value put on its I/O data bus in a latch.
+// reading a write-only register returns that value, and every bit slowly
+// fades to 0 unless it gets refreshed (roughly 600ms on real hardware).
+const DECAY_FRAMES: u16 = 36;
+
+#[derive(Debug, Default)]
+pub struct OpenBus {
+    value: u8,
+    // frames left until each bit decays
+    decay: [u16; 8],
+}
+
+impl OpenBus {
+    pub fn new() -> Self {
+        OpenBus::default()
+    }
+
+    pub fn get(&self) -> u8 {
+        self.value
+    }
+
+    /// refresh the bits selected by `mask` with `value`.
+    pub fn set(&mut self, value: u8, mask: u8) {
+        self.value = (self.value & !mask) | (value & mask);
+        for bit in 0..8 {
+            if mask & (1 << bit) != 0 {
+                self.decay[bit] = DECAY_FRAMES;
+            }
+        }
+    }
+
+    /// called once per frame.
+    pub fn tick_frame(&mut self) {
+        for bit in 0..8 {
+            if self.decay[bit] == 0 {
+                continue;
+            }
+            self.decay[bit] -= 1;
+            if self.decay[bit] == 0 {
+                self.value &= !(1 << bit);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bits_decay_unless_refreshed() {
+        let mut bus = OpenBus::new();
+        bus.set(0xff, 0xff);
+        for _ in 0..DECAY_FRAMES - 1 {
+            bus.tick_frame();
+        }
+        bus.set(0x0f, 0x0f);
+        assert_eq!(bus.get(), 0xff);
+
+        bus.tick_frame();
+        assert_eq!(bus.get(), 0x0f);
+
+        for _ in 0..DECAY_FRAMES {
+            bus.tick_frame();
+        }
+        assert_eq!(bus.get(), 0);
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use crate::rendering::overscan::Overscan;
+
+// https://www.nesdev.org/wiki/Cycle_reference_chart
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Region {
+    NTSC,
+    PAL,
+    // famiclone timing: PAL frame with NTSC-like CPU/PPU ratio
+    Dendy,
+}
+
+impl Region {
+    pub fn parse(value: &str) -> Result<Region, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "ntsc" => Ok(Region::NTSC),
+            "pal" => Ok(Region::PAL),
+            "dendy" => Ok(Region::Dendy),
+            _ => Err(format!(
+                "region expects ntsc, pal or dendy but got {}",
+                value
+            )),
+        }
+    }
+
+    /// scanlines per frame, including the pre-render line.
+    pub fn scanlines_per_frame(&self) -> u16 {
+        match self {
+            Region::NTSC => 262,
+            Region::PAL | Region::Dendy => 312,
+        }
+    }
+
+    pub fn vblank_scanlines(&self) -> u16 {
+        match self {
+            Region::NTSC | Region::Dendy => 20,
+            Region::PAL => 70,
+        }
+    }
+
+    /// scanline where the vblank flag is set and NMI fires.
+    /// the last scanline of the frame is the pre-render line.
+    pub fn vblank_start_scanline(&self) -> u16 {
+        self.scanlines_per_frame() - self.vblank_scanlines() - 1
+    }
+
+    /// PPU dots per CPU cycle as (numerator, denominator).
+    /// 3 on NTSC and Dendy, 3.2 on PAL.
+    pub fn ppu_cpu_clock_ratio(&self) -> (usize, usize) {
+        match self {
+            Region::NTSC | Region::Dendy => (3, 1),
+            Region::PAL => (16, 5),
+        }
+    }
+
+    pub fn cpu_clock_hz(&self) -> u32 {
+        match self {
+            Region::NTSC => 1_789_773,
+            Region::PAL => 1_662_607,
+            Region::Dendy => 1_773_448,
+        }
+    }
+
+    pub fn frames_per_second(&self) -> f64 {
+        match self {
+            Region::NTSC => 60.0988,
+            Region::PAL => 50.0070,
+            Region::Dendy => 50.0,
+        }
+    }
+
+    pub fn frame_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.frames_per_second())
+    }
+
+    pub fn default_overscan(&self) -> Overscan {
+        match self {
+            Region::NTSC => Overscan::ntsc(),
+            Region::PAL | Region::Dendy => Overscan::pal(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vblank_start_scanline() {
+        assert_eq!(Region::NTSC.vblank_start_scanline(), 241);
+        assert_eq!(Region::PAL.vblank_start_scanline(), 241);
+        assert_eq!(Region::Dendy.vblank_start_scanline(), 291);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Region::parse("PAL"), Ok(Region::PAL));
+        assert_eq!(Region::parse("dendy"), Ok(Region::Dendy));
+        assert!(Region::parse("secam").is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use std::usize;
 
 use crate::{
+    cartridge::cdl,
     ppu::{register::status_register::StatusRegister, NesPPU, PPUMirroring},
     rendering::{
         frame::Frame,
@@ -18,7 +19,6 @@ pub fn render_name_table(
     shift_x: isize,
     shift_y: isize,
     draw_rect: &Rect,
-    scanlines: usize,
 ) {
     let bank = ppu.control_reg.background_pattern_addr();
 
@@ -39,8 +39,7 @@ pub fn render_name_table(
         let tile_idx = name_table[i] as u16;
 
         let start = (bank + tile_idx * 16) as usize;
-        let end = (bank + tile_idx * 16 + 15) as usize;
-        let tile = &ppu.chr_rom[start..=end];
+        let tile = fetch_tile(ppu, start, ppu.mask_reg.show_background());
         let palette = bg_pallette(ppu, attrs_table, tile_column, tile_row);
 
         for y in 0..8 {
@@ -73,13 +72,22 @@ pub fn render_name_table(
                         && y >= draw_rect.y1
                         && y < draw_rect.y2
                     {
-                        frame.set_pixel(x, y - 8, rgb)
+                        frame.set_pixel(x, y, rgb)
                     }
                 }
             }
         }
     }
 }
+// the 16 bytes of the tile at `start` in the pattern tables,
+// logged as rendered when its layer is `shown`
+fn fetch_tile(ppu: &NesPPU, start: usize, shown: bool) -> &[u8] {
+    if let (true, Some(log)) = (shown, ppu.chr_log.borrow_mut().as_mut()) {
+        cdl::mark_chr(log, start, 16, cdl::RENDERED);
+    }
+    &ppu.chr_rom[start..start + 16]
+}
+
 pub fn render(ppu: &NesPPU, frame: &mut Frame, scanlines: u16) {
     let scroll_x = (ppu.scroll_register.scroll_x) as usize;
     let scroll_y = (ppu.scroll_register.scroll_y) as usize;
@@ -105,7 +113,8 @@ pub fn render(ppu: &NesPPU, frame: &mut Frame, scanlines: u16) {
                 panic!("not implemented yet")
             }
         };
-    let draw_rect = Rect::new(0, scanlines as usize, 256, scanlines as usize + 8);
+    // draw the 8 lines the PPU has just finished.
+    let draw_rect = Rect::new(0, scanlines as usize - 8, 256, scanlines as usize);
 
     render_name_table(
         ppu,
@@ -120,7 +129,6 @@ pub fn render(ppu: &NesPPU, frame: &mut Frame, scanlines: u16) {
         -(scroll_x as isize),
         -(scroll_y as isize),
         &draw_rect,
-        scanlines as usize,
     );
     if scroll_x > 0 {
         render_name_table(
@@ -131,7 +139,6 @@ pub fn render(ppu: &NesPPU, frame: &mut Frame, scanlines: u16) {
             (256 - scroll_x) as isize,
             0,
             &draw_rect,
-            scanlines as usize,
         );
     } else if scroll_y > 0 {
         render_name_table(
@@ -142,7 +149,6 @@ pub fn render(ppu: &NesPPU, frame: &mut Frame, scanlines: u16) {
             0,
             (240 - scroll_y) as isize,
             &draw_rect,
-            scanlines as usize,
         );
     }
 
@@ -160,12 +166,15 @@ pub fn render(ppu: &NesPPU, frame: &mut Frame, scanlines: u16) {
         let pallette_idx = attrs & 0b11;
         let sprite_palette = sprite_pallete(ppu, pallette_idx);
 
-        let start = (bank + tile_idx * 16) as usize;
-        let end = (bank + tile_idx * 16 + 15) as usize;
-        let tile = &ppu.chr_rom[start..=end];
         if tile_y == 0 && tile_x == 0 {
             continue;
         }
+        // y $ef-$ff hides a sprite, the others are drawn with the lines they cover
+        if tile_y >= 0xef || tile_y + 8 <= draw_rect.y1 || tile_y >= draw_rect.y2 {
+            continue;
+        }
+        let start = (bank + tile_idx * 16) as usize;
+        let tile = fetch_tile(ppu, start, ppu.mask_reg.show_sprites());
         for y in 0..=7 {
             let mut upper = tile[y];
             let mut lower = tile[y + 8];
@@ -181,10 +190,10 @@ pub fn render(ppu: &NesPPU, frame: &mut Frame, scanlines: u16) {
                     _ => panic!("can't be"),
                 };
                 match (flip_horizontal, flip_vertical) {
-                    (false, false) => frame.set_pixel(tile_x + x, tile_y + y - 8, rgb),
-                    (true, false) => frame.set_pixel(tile_x + 7 - x, tile_y + y - 8, rgb),
-                    (false, true) => frame.set_pixel(tile_x + x, tile_y + 7 - y - 8, rgb),
-                    (true, true) => frame.set_pixel(tile_x + 7 - x, tile_y + 7 - y - 8, rgb),
+                    (false, false) => frame.set_pixel(tile_x + x, tile_y + y, rgb),
+                    (true, false) => frame.set_pixel(tile_x + 7 - x, tile_y + y, rgb),
+                    (false, true) => frame.set_pixel(tile_x + x, tile_y + 7 - y, rgb),
+                    (true, true) => frame.set_pixel(tile_x + 7 - x, tile_y + 7 - y, rgb),
                 };
             }
         }
@@ -1,4 +1,6 @@
 pub mod frame;
+pub mod image;
+pub mod overscan;
 pub mod rect;
 pub mod render_util;
 
@@ -0,0 +1,141 @@
+use super::{frame::Frame, rect::Rect};
+
+// the PPU always outputs 256x240 pixels, but a CRT hides a few lines/columns
+// at the edges. the renderer draws the full picture and the overscan is only
+// cut out when the frame is presented (window, screenshot, hash, ...).
+pub const SCREEN_WIDTH: usize = 256;
+pub const SCREEN_HEIGHT: usize = 240;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Overscan {
+    pub top: usize,
+    pub bottom: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Overscan {
+    pub fn new(top: usize, bottom: usize, left: usize, right: usize) -> Self {
+        assert!(
+            top + bottom < SCREEN_HEIGHT,
+            "overscan crops the whole height"
+        );
+        assert!(
+            left + right < SCREEN_WIDTH,
+            "overscan crops the whole width"
+        );
+        Overscan {
+            top,
+            bottom,
+            left,
+            right,
+        }
+    }
+
+    /// keep all 240 lines.
+    pub fn none() -> Self {
+        Overscan::new(0, 0, 0, 0)
+    }
+
+    /// NTSC TVs usually hide the top and bottom 8 lines.
+    pub fn ntsc() -> Self {
+        Overscan::new(8, 8, 0, 0)
+    }
+
+    /// PAL TVs show (almost) the whole picture, only the first line is hidden.
+    pub fn pal() -> Self {
+        Overscan::new(1, 0, 0, 0)
+    }
+
+    /// parse `none`, `ntsc`, `pal` or `top,bottom,left,right`.
+    pub fn parse(value: &str) -> Result<Overscan, String> {
+        match value {
+            "none" => return Ok(Overscan::none()),
+            "ntsc" => return Ok(Overscan::ntsc()),
+            "pal" => return Ok(Overscan::pal()),
+            _ => {}
+        }
+        let values = value
+            .split(',')
+            .map(|v| v.trim().parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|e| format!("invalid overscan value {}: {}", value, e))?;
+        match values[..] {
+            [top, bottom, left, right]
+                if top + bottom < SCREEN_HEIGHT && left + right < SCREEN_WIDTH =>
+            {
+                Ok(Overscan::new(top, bottom, left, right))
+            }
+            [_, _, _, _] => Err(format!("overscan {} crops the whole screen", value)),
+            _ => Err(format!(
+                "overscan expects none, ntsc, pal or top,bottom,left,right but got {}",
+                value
+            )),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        SCREEN_WIDTH - self.left - self.right
+    }
+    pub fn height(&self) -> usize {
+        SCREEN_HEIGHT - self.top - self.bottom
+    }
+
+    /// visible area of the frame.
+    pub fn rect(&self) -> Rect {
+        Rect::new(
+            self.left,
+            self.top,
+            SCREEN_WIDTH - self.right,
+            SCREEN_HEIGHT - self.bottom,
+        )
+    }
+
+    /// copy the visible area of the frame into a tightly packed RGB24 buffer.
+    pub fn crop(&self, frame: &Frame) -> Vec<u8> {
+        let rect = self.rect();
+        let mut result = Vec::with_capacity(self.width() * self.height() * 3);
+        for y in rect.y1..rect.y2 {
+            for x in rect.x1..rect.x2 {
+                let (r, g, b) = frame.get_pixel(x, y);
+                result.push(r);
+                result.push(g);
+                result.push(b);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_crop_keeps_visible_area() {
+        let mut frame = Frame::new();
+        frame.set_pixel(0, 7, (1, 1, 1));
+        frame.set_pixel(0, 8, (2, 2, 2));
+        frame.set_pixel(255, 231, (3, 3, 3));
+        frame.set_pixel(255, 232, (4, 4, 4));
+
+        let overscan = Overscan::ntsc();
+        let data = overscan.crop(&frame);
+        assert_eq!(data.len(), 256 * 224 * 3);
+        assert_eq!(&data[0..3], &[2, 2, 2]);
+        assert_eq!(&data[data.len() - 3..], &[3, 3, 3]);
+
+        let full = Overscan::none().crop(&frame);
+        assert_eq!(full.len(), 256 * 240 * 3);
+        assert_eq!(&full[7 * 256 * 3..7 * 256 * 3 + 3], &[1, 1, 1]);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Overscan::parse("ntsc"), Ok(Overscan::ntsc()));
+        assert_eq!(Overscan::parse("8,8,4,4"), Ok(Overscan::new(8, 8, 4, 4)));
+        assert!(Overscan::parse("8,8").is_err());
+        assert!(Overscan::parse("200,200,0,0").is_err());
+        assert!(Overscan::parse("abc").is_err());
+    }
+}
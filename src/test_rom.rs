@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use crate::bus::Bus;
+use crate::cartridge::rom::ROM;
+use crate::cpu_internals::cpu::CPU;
+use crate::cpu_internals::cpu_bus::CpuBus;
+use crate::cpu_internals::jam::JamPolicy;
+
+// blargg's test roms (instr_test, ppu_vbl_nmi, apu_test, ...) report through PRG RAM:
+// $6000 status: $80 running, $81 asks for a reset, $00-$7F result code (0 = passed)
+// $6001-$6003 DE B0 61 once the values are valid
+// $6004 zero terminated text output
+const STATUS: u16 = 0x6000;
+const SIGNATURE: u16 = 0x6001;
+const SIGNATURE_BYTES: [u8; 3] = [0xde, 0xb0, 0x61];
+const MESSAGE: u16 = 0x6004;
+const MESSAGE_END: u16 = 0x7fff;
+
+const RUNNING: u8 = 0x80;
+const RESET_REQUESTED: u8 = 0x81;
+// the rom wants the reset button pressed at least 100ms after asking
+const RESET_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, PartialEq)]
+pub enum TestRomStatus {
+    Passed,
+    Failed(u8),
+    // still running (or never wrote the signature) when the time ran out
+    TimedOut,
+}
+
+#[derive(Debug)]
+pub struct TestRomResult {
+    pub status: TestRomStatus,
+    pub message: String,
+}
+
+impl TestRomResult {
+    pub fn passed(&self) -> bool {
+        self.status == TestRomStatus::Passed
+    }
+}
+
+/// run `rom` headless until it reports a result, or `timeout` of emulated time went by.
+pub fn run_test_rom(rom: ROM, timeout: Duration) -> Result<TestRomResult, String> {
+    // the bus only maps NROM, a bank switch write would panic
+    if rom.mapper != 0 {
+        return Err(format!("mapper {} is not supported", rom.mapper));
+    }
+    let cpu_clock_hz = rom.region.cpu_clock_hz() as f64;
+    let max_cycles = (timeout.as_secs_f64() * cpu_clock_hz) as usize;
+    let reset_delay = (RESET_DELAY.as_secs_f64() * cpu_clock_hz) as usize;
+
+    let bus = Bus::new(rom, |_, _, _| {});
+    let mut cpu = CPU::new(bus);
+    cpu.jam_policy = JamPolicy::Error;
+    cpu.reset();
+
+    let mut result = None;
+    let mut reset_at = None;
+    let mut reset_done = false;
+    cpu.run_with_callback(|cpu| {
+        if cpu.bus.cycles >= max_cycles {
+            cpu.halted = true;
+            return;
+        }
+        if let Some(at) = reset_at {
+            if cpu.bus.cycles >= at {
+                reset_at = None;
+                reset_done = true;
+                cpu.reset();
+            }
+            return;
+        }
+        if !has_signature(cpu) {
+            return;
+        }
+        match cpu.bus.peek(STATUS) {
+            RUNNING => reset_done = false,
+            // $81 stays there until the rom is running again after the reset
+            RESET_REQUESTED if reset_done => {}
+            RESET_REQUESTED => reset_at = Some(cpu.bus.cycles + reset_delay),
+            code => {
+                result = Some(code);
+                cpu.halted = true;
+            }
+        }
+    })?;
+
+    let status = match result {
+        Some(0) => TestRomStatus::Passed,
+        Some(code) => TestRomStatus::Failed(code),
+        None => TestRomStatus::TimedOut,
+    };
+    let message = if has_signature(&cpu) {
+        read_message(&cpu)
+    } else {
+        String::new()
+    };
+    Ok(TestRomResult { status, message })
+}
+
+fn has_signature(cpu: &CPU<Bus>) -> bool {
+    (0..3).all(|i| cpu.bus.peek(SIGNATURE + i) == SIGNATURE_BYTES[i as usize])
+}
+
+fn read_message(cpu: &CPU<Bus>) -> String {
+    let bytes = (MESSAGE..=MESSAGE_END)
+        .map(|addr| cpu.bus.peek(addr))
+        .take_while(|&byte| byte != 0)
+        .collect::<Vec<u8>>();
+    String::from_utf8_lossy(&bytes).trim_end().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // NROM with `program` at $8000, the reset vector pointing at it
+    fn rom_with_program(program: &[u8]) -> ROM {
+        rom_with_mapper(program, 0)
+    }
+
+    fn rom_with_mapper(program: &[u8], mapper: u8) -> ROM {
+        let mut raw = vec![
+            0x4e,
+            0x45,
+            0x53,
+            0x1a,
+            0x01,
+            0x01,
+            mapper << 4,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ];
+        let mut prg_rom = vec![0xea; 0x4000];
+        prg_rom[..program.len()].copy_from_slice(program);
+        prg_rom[0x3ffc] = 0x00;
+        prg_rom[0x3ffd] = 0x80;
+        raw.extend(prg_rom);
+        raw.extend(vec![0; 0x2000]);
+        ROM::new(&raw).unwrap()
+    }
+
+    // LDA #value, STA addr
+    fn store(program: &mut Vec<u8>, addr: u16, value: u8) {
+        program.extend([0xa9, value, 0x8d, addr as u8, (addr >> 8) as u8]);
+    }
+
+    // CLV, BVC to itself
+    fn hang(program: &mut Vec<u8>) {
+        program.extend([0xb8, 0x50, 0xfe]);
+    }
+
+    // mark the test running, write the signature, "ok" as message and `code` as status
+    fn report(program: &mut Vec<u8>, code: u8) {
+        store(program, 0x6000, RUNNING);
+        store(program, 0x6001, 0xde);
+        store(program, 0x6002, 0xb0);
+        store(program, 0x6003, 0x61);
+        store(program, 0x6004, b'o');
+        store(program, 0x6005, b'k');
+        store(program, 0x6006, 0);
+        store(program, 0x6000, code);
+        hang(program);
+    }
+
+    fn reporting_program(code: u8) -> Vec<u8> {
+        let mut program = vec![];
+        report(&mut program, code);
+        program
+    }
+
+    #[test]
+    fn test_passed() {
+        let rom = rom_with_program(&reporting_program(0));
+        let result = run_test_rom(rom, Duration::from_secs(1)).unwrap();
+        assert_eq!(result.status, TestRomStatus::Passed);
+        assert_eq!(result.message, "ok");
+    }
+
+    #[test]
+    fn test_failed() {
+        let rom = rom_with_program(&reporting_program(3));
+        let result = run_test_rom(rom, Duration::from_secs(1)).unwrap();
+        assert_eq!(result.status, TestRomStatus::Failed(3));
+        assert!(!result.passed());
+    }
+
+    #[test]
+    fn test_timed_out() {
+        let rom = rom_with_program(&reporting_program(RUNNING));
+        let result = run_test_rom(rom, Duration::from_millis(10)).unwrap();
+        assert_eq!(result.status, TestRomStatus::TimedOut);
+    }
+
+    #[test]
+    fn test_reset_requested() {
+        // ask for a reset on the first run, pass after it
+        let mut first_run = vec![];
+        store(&mut first_run, 0x6100, 1);
+        report(&mut first_run, RESET_REQUESTED);
+        // LDA $6100, BNE after_reset
+        let mut program = vec![0xad, 0x00, 0x61, 0xd0, first_run.len() as u8];
+        program.extend(first_run);
+        report(&mut program, 0);
+
+        let rom = rom_with_program(&program);
+        let result = run_test_rom(rom, Duration::from_secs(1)).unwrap();
+        assert_eq!(result.status, TestRomStatus::Passed);
+    }
+
+    #[test]
+    fn test_unsupported_mapper() {
+        let rom = rom_with_mapper(&reporting_program(0), 1);
+        let result = run_test_rom(rom, Duration::from_secs(1));
+        assert_eq!(result.unwrap_err(), "mapper 1 is not supported");
+    }
+}
@@ -0,0 +1,273 @@
+use crate::cartridge::cdl::CodeDataLog;
+use crate::cartridge::rom::ROM;
+use crate::cpu_internals::opscodes::AddressingMode;
+use crate::cpu_internals::opscodes::OPCODES;
+use crate::utils::symbols::Symbols;
+
+const PRG_BANK_SIZE: usize = 0x4000;
+
+#[derive(Debug, PartialEq)]
+pub struct Instruction {
+    pub addr: u16,
+    pub bytes: Vec<u8>,
+    // illegal opcodes start with `*`
+    pub mnemonic: &'static str,
+    // e.g. `($33),Y`, empty for implied instructions
+    pub operand: String,
+}
+
+impl Instruction {
+    /// `LDA ($33),Y`
+    pub fn text(&self) -> String {
+        format!("{} {}", self.mnemonic, self.operand)
+            .trim_end()
+            .to_string()
+    }
+
+    /// address of the instruction right after this one.
+    pub fn next(&self) -> u16 {
+        self.addr.wrapping_add(self.bytes.len() as u16)
+    }
+
+    /// like `to_string` with the operand address replaced by its label.
+    pub fn labeled(&self, symbols: &Symbols) -> String {
+        self.line(&symbols.label_operand(&self.operand))
+    }
+
+    fn line(&self, operand: &str) -> String {
+        let hex = self
+            .bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let line = format!(
+            "{:04X}  {:8} {:>4} {}",
+            self.addr, hex, self.mnemonic, operand
+        );
+        line.trim_end().to_string()
+    }
+}
+
+// `1234  A9 10     LDA #$10`, the first columns of a trace line.
+// the mnemonic is right aligned so the `*` of illegal opcodes sticks out.
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.line(&self.operand))
+    }
+}
+
+/// decode the instruction at `addr`, `read` gives the byte at an address
+/// (e.g. `|addr| bus.peek(addr)`).
+pub fn disassemble<F>(read: F, addr: u16) -> Instruction
+where
+    F: Fn(u16) -> u8,
+{
+    let ops = OPCODES[read(addr) as usize];
+    let bytes = (0..ops.bytes_len as u16)
+        .map(|i| read(addr.wrapping_add(i)))
+        .collect::<Vec<u8>>();
+    let byte = bytes.get(1).copied().unwrap_or(0);
+    let word = u16::from_le_bytes([byte, bytes.get(2).copied().unwrap_or(0)]);
+
+    let operand = match (&ops.mode, ops.bytes_len) {
+        (AddressingMode::Immediate, _) => format!("#${:02X}", byte),
+        (AddressingMode::ZeroPage, _) => format!("${:02X}", byte),
+        (AddressingMode::ZeroPage_X, _) => format!("${:02X},X", byte),
+        (AddressingMode::ZeroPage_Y, _) => format!("${:02X},Y", byte),
+        (AddressingMode::Absolute, _) => format!("${:04X}", word),
+        (AddressingMode::Absolute_X, _) => format!("${:04X},X", word),
+        (AddressingMode::Absolute_Y, _) => format!("${:04X},Y", word),
+        (AddressingMode::Indirect_X, _) => format!("(${:02X},X)", byte),
+        (AddressingMode::Indirect_Y, _) => format!("(${:02X}),Y", byte),
+        (AddressingMode::Relative, _) => {
+            let target = addr.wrapping_add(2).wrapping_add(byte as i8 as u16);
+            format!("${:04X}", target)
+        }
+        (AddressingMode::NonAddressing, 3) if ops.code == 0x6c => format!("(${:04X})", word),
+        (AddressingMode::NonAddressing, 3) => format!("${:04X}", word),
+        (AddressingMode::NonAddressing, 2) => format!("${:02X}", byte),
+        (AddressingMode::NonAddressing, _) => match ops.code {
+            0x0a | 0x4a | 0x2a | 0x6a => "A".to_string(),
+            _ => String::new(),
+        },
+    };
+    Instruction {
+        addr,
+        bytes,
+        mnemonic: ops.name,
+        operand,
+    }
+}
+
+/// `count` instructions starting at `addr`.
+pub fn disassemble_range<F>(read: F, addr: u16, count: usize) -> Vec<Instruction>
+where
+    F: Fn(u16) -> u8,
+{
+    let mut result = Vec::with_capacity(count);
+    let mut addr = addr;
+    for _ in 0..count {
+        let instruction = disassemble(&read, addr);
+        addr = instruction.next();
+        result.push(instruction);
+    }
+    result
+}
+
+/// cpu address a 16kib PRG bank is seen at. NROM only: a single bank is
+/// mirrored at $8000 and $C000 and listed at $C000 where the vectors are.
+pub fn prg_bank_base(rom: &ROM, bank: usize) -> u16 {
+    if rom.prg_rom.len() <= PRG_BANK_SIZE {
+        0xc000
+    } else {
+        0x8000 + (bank * PRG_BANK_SIZE) as u16
+    }
+}
+
+// `.byte` lines hold up to 8 bytes
+const BYTES_PER_LINE: usize = 8;
+
+fn byte_line(addr: usize, data: &[u8]) -> String {
+    let bytes = data
+        .iter()
+        .map(|b| format!("${:02X}", b))
+        .collect::<Vec<String>>();
+    format!("{:04X}  .byte {}", addr, bytes.join(","))
+}
+
+/// listing of a PRG bank, a `label:` line before each labeled address.
+/// bytes the code/data log only saw as data are listed as `.byte`, everything
+/// else is decoded as code. the bytes left at the end are `.byte` too.
+pub fn disassemble_prg_bank(
+    rom: &ROM,
+    bank: usize,
+    symbols: &Symbols,
+    cdl: Option<&CodeDataLog>,
+) -> Result<Vec<String>, String> {
+    let banks = rom.prg_rom.len().div_ceil(PRG_BANK_SIZE);
+    if bank >= banks {
+        return Err(format!(
+            "prg bank {} out of range, the rom has {}",
+            bank, banks
+        ));
+    }
+    let data =
+        &rom.prg_rom[bank * PRG_BANK_SIZE..((bank + 1) * PRG_BANK_SIZE).min(rom.prg_rom.len())];
+    let base = prg_bank_base(rom, bank) as usize;
+    let end = base + data.len();
+    let is_data =
+        |addr: usize| cdl.is_some_and(|log| log.is_data(bank * PRG_BANK_SIZE + addr - base));
+    let read = |addr: u16| {
+        (addr as usize)
+            .checked_sub(base)
+            .and_then(|i| data.get(i).copied())
+            .unwrap_or(0)
+    };
+
+    let mut lines = vec![];
+    let mut addr = base;
+    while addr < end {
+        if let Some(label) = symbols.get(addr as u16) {
+            lines.push(format!("{}:", label));
+        }
+        if is_data(addr) {
+            // a run of data, cut at the next label
+            let mut next = addr + 1;
+            while next < end
+                && next - addr < BYTES_PER_LINE
+                && is_data(next)
+                && symbols.get(next as u16).is_none()
+            {
+                next += 1;
+            }
+            lines.push(byte_line(addr, &data[addr - base..next - base]));
+            addr = next;
+            continue;
+        }
+        let instruction = disassemble(read, addr as u16);
+        if addr + instruction.bytes.len() > end {
+            lines.push(byte_line(addr, &data[addr - base..]));
+            break;
+        }
+        lines.push(instruction.labeled(symbols));
+        addr += instruction.bytes.len();
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cartridge::cdl;
+
+    fn read_from(program: &'static [u8]) -> impl Fn(u16) -> u8 {
+        move |addr| program.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    #[test]
+    fn test_disassemble() {
+        // LDA ($33),Y / ASL A / BNE -5 / JMP ($0200) / *NOP $A9
+        let program = &[0xb1, 0x33, 0x0a, 0xd0, 0xfb, 0x6c, 0x00, 0x02, 0x04, 0xa9];
+        let lines = disassemble_range(read_from(program), 0, 5)
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            lines,
+            [
+                "0000  B1 33     LDA ($33),Y",
+                "0002  0A        ASL A",
+                "0003  D0 FB     BNE $0000",
+                "0005  6C 00 02  JMP ($0200)",
+                "0008  04 A9    *NOP $A9",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_prg_bank() {
+        let mut raw = vec![
+            0x4e, 0x45, 0x53, 0x1a, 0x01, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        let mut prg_rom = vec![0xea; 0x4000];
+        // LDA $2002 / BPL -5, a JSR cut by the end of the bank
+        prg_rom[..5].copy_from_slice(&[0xad, 0x02, 0x20, 0x10, 0xfb]);
+        prg_rom[0x3ffe] = 0x20;
+        prg_rom[0x3fff] = 0x00;
+        raw.extend(prg_rom);
+        raw.extend(vec![0; 0x2000]);
+        let rom = ROM::new(&raw).unwrap();
+
+        let mut symbols = Symbols::new();
+        symbols.insert(0xc000, "wait_vblank");
+        symbols.insert(0x2002, "PPUSTATUS");
+        let lines = disassemble_prg_bank(&rom, 0, &symbols, None).unwrap();
+        assert_eq!(
+            lines[..3],
+            [
+                "wait_vblank:",
+                "C000  AD 02 20  LDA PPUSTATUS",
+                "C003  10 FB     BPL wait_vblank",
+            ]
+        );
+        assert_eq!(lines.last().unwrap(), "FFFE  .byte $20,$00");
+        assert!(disassemble_prg_bank(&rom, 1, &symbols, None).is_err());
+
+        // the 10 bytes after the loop were only read as data
+        let mut cdl = CodeDataLog::new(rom.prg_rom.len(), rom.chr_rom.len());
+        cdl.prg[..5].fill(cdl::CODE);
+        cdl.prg[5..15].fill(cdl::DATA);
+        let lines = disassemble_prg_bank(&rom, 0, &Symbols::new(), Some(&cdl)).unwrap();
+        assert_eq!(
+            lines[..5],
+            [
+                "C000  AD 02 20  LDA $2002",
+                "C003  10 FB     BPL $C000",
+                "C005  .byte $EA,$EA,$EA,$EA,$EA,$EA,$EA,$EA",
+                "C00D  .byte $EA,$EA",
+                "C00F  EA        NOP",
+            ]
+        );
+    }
+}
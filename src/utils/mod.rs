@@ -1,2 +1,7 @@
+pub mod disasm;
+pub mod show_nametable;
+pub mod show_oam;
 pub mod show_tile;
+pub mod symbols;
 pub mod trace;
+pub mod viewer;
@@ -0,0 +1,222 @@
+use std::path::PathBuf;
+
+use sdl2::{
+    event::{Event, WindowEvent},
+    keyboard::Keycode,
+    VideoSubsystem,
+};
+
+use crate::{
+    ppu::NesPPU,
+    rendering::image::Image,
+    utils::show_tile::{draw_tile, palette_colors},
+    utils::viewer::ViewerWindow,
+};
+
+// the four logical nametables, $2000 $2400 on top and $2800 $2C00 below
+pub const NAMETABLES_WIDTH: usize = 512;
+pub const NAMETABLES_HEIGHT: usize = 480;
+
+const GRID_COLOR: (u8, u8, u8) = (0x40, 0x40, 0x40);
+const VIEWPORT_COLOR: (u8, u8, u8) = (0xff, 0x00, 0xff);
+
+// the tile under a pixel of the nametables image
+#[derive(Debug, PartialEq)]
+pub struct TileInfo {
+    // $2000-$2FFF, before mirroring
+    pub addr: u16,
+    pub index: u8,
+    pub attribute_addr: u16,
+    pub palette: u8,
+}
+
+fn read_vram(ppu: &NesPPU, addr: u16) -> u8 {
+    // four screen carts bring their own 2kib the ppu doesn't have yet
+    ppu.vram
+        .get(ppu.get_mirror_vram_addr(addr) as usize)
+        .copied()
+        .unwrap_or(0)
+}
+
+pub fn tile_at(ppu: &NesPPU, x: usize, y: usize) -> TileInfo {
+    let table = (y / 240 % 2 * 2 + x / 256 % 2) as u16;
+    let column = (x % 256 / 8) as u16;
+    let row = (y % 240 / 8) as u16;
+    let base = 0x2000 + table * 0x400;
+    let addr = base + row * 32 + column;
+    let attribute_addr = base + 0x3c0 + row / 4 * 8 + column / 4;
+    // 2 bits per 16x16 area: top left, top right, bottom left, bottom right
+    let shift = (row % 4 / 2) * 4 + (column % 4 / 2) * 2;
+    TileInfo {
+        addr,
+        index: read_vram(ppu, addr),
+        attribute_addr,
+        palette: (read_vram(ppu, attribute_addr) >> shift) & 0b11,
+    }
+}
+
+/// top left corner of the screen in the nametables image, from PPUCTRL and PPUSCROLL.
+pub fn scroll_origin(ppu: &NesPPU) -> (usize, usize) {
+    let table = ((ppu.control_reg.nametable_addr() - 0x2000) / 0x400) as usize;
+    let x = table % 2 * 256 + ppu.scroll_register.scroll_x as usize;
+    let y = table / 2 * 240 + ppu.scroll_register.scroll_y as usize;
+    (x, y)
+}
+
+/// all four nametables with the background pattern table, the attribute grid
+/// (16x16 areas sharing a palette) when `grid` is set and the visible 256x240
+/// window outlined. the window wraps around the edges like the scrolling does.
+pub fn nametables(ppu: &NesPPU, grid: bool) -> Image {
+    let mut image = Image::new(NAMETABLES_WIDTH, NAMETABLES_HEIGHT);
+    let bank = ppu.control_reg.background_pattern_addr();
+    for y in (0..NAMETABLES_HEIGHT).step_by(8) {
+        for x in (0..NAMETABLES_WIDTH).step_by(8) {
+            let tile = tile_at(ppu, x, y);
+            let colors = palette_colors(ppu, tile.palette);
+            draw_tile(
+                &mut image,
+                ppu,
+                bank + tile.index as u16 * 16,
+                x,
+                y,
+                &colors,
+            );
+        }
+    }
+    if grid {
+        for y in 0..NAMETABLES_HEIGHT {
+            for x in 0..NAMETABLES_WIDTH {
+                if x % 16 == 0 || y % 240 % 16 == 0 {
+                    image.set_pixel(x, y, GRID_COLOR);
+                }
+            }
+        }
+    }
+    let (origin_x, origin_y) = scroll_origin(ppu);
+    for i in 0..256 {
+        let x = (origin_x + i) % NAMETABLES_WIDTH;
+        image.set_pixel(x, origin_y % NAMETABLES_HEIGHT, VIEWPORT_COLOR);
+        image.set_pixel(x, (origin_y + 239) % NAMETABLES_HEIGHT, VIEWPORT_COLOR);
+    }
+    for i in 0..240 {
+        let y = (origin_y + i) % NAMETABLES_HEIGHT;
+        image.set_pixel(origin_x % NAMETABLES_WIDTH, y, VIEWPORT_COLOR);
+        image.set_pixel((origin_x + 255) % NAMETABLES_WIDTH, y, VIEWPORT_COLOR);
+    }
+    image
+}
+
+// live window of the nametables, redrawn every frame.
+// G toggles the attribute grid, E saves a PNG, the title describes the hovered tile.
+pub struct NametableViewer {
+    window: ViewerWindow,
+    grid: bool,
+    hover: Option<(usize, usize)>,
+}
+
+impl NametableViewer {
+    pub fn new(video: &VideoSubsystem, export_path: PathBuf) -> Result<Self, String> {
+        let window = ViewerWindow::new(
+            video,
+            "nametables",
+            NAMETABLES_WIDTH,
+            NAMETABLES_HEIGHT,
+            1,
+            export_path,
+        )?;
+        Ok(NametableViewer {
+            window,
+            grid: false,
+            hover: None,
+        })
+    }
+
+    pub fn update(&mut self, ppu: &NesPPU) -> Result<(), String> {
+        if let Some((x, y)) = self.hover {
+            let tile = tile_at(ppu, x, y);
+            self.window.set_title(&format!(
+                "nametables - tile ${:02X} at ${:04X}, palette {} from ${:04X}",
+                tile.index, tile.addr, tile.palette, tile.attribute_addr
+            ));
+        }
+        self.window.show(nametables(ppu, self.grid))
+    }
+
+    /// true when the event was for this window.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        if !self.window.handle_common(event) {
+            return false;
+        }
+        match event {
+            Event::Window {
+                win_event: WindowEvent::Leave,
+                ..
+            } => {
+                self.hover = None;
+                self.window.set_title("nametables");
+            }
+            Event::MouseMotion { x, y, .. } if *x >= 0 && *y >= 0 => {
+                self.hover = Some((*x as usize, *y as usize));
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::G),
+                ..
+            } => self.grid = !self.grid,
+            _ => {}
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ppu::PPUMirroring;
+    use crate::ppu::PPU;
+    use crate::rendering::SYSTEM_PALLETE;
+
+    #[test]
+    fn test_tile_at() {
+        let mut ppu = NesPPU::new(vec![0; 0x2000], PPUMirroring::Vertical);
+        // $2C00 mirrors $2400 on vertical mirroring
+        ppu.vram[0x400 + 66] = 0x42;
+        // bottom right area of the first attribute byte
+        ppu.vram[0x400 + 0x3c0] = 0b1100_0000;
+        let tile = tile_at(&ppu, 256 + 16 + 2, 240 + 16 + 7);
+        assert_eq!(
+            tile,
+            TileInfo {
+                addr: 0x2c42,
+                index: 0x42,
+                attribute_addr: 0x2fc0,
+                palette: 3,
+            }
+        );
+        assert_eq!(tile_at(&ppu, 256 + 16, 240).palette, 0);
+    }
+
+    #[test]
+    fn test_nametables() {
+        let mut chr_rom = vec![0; 0x2000];
+        // tile 1 is solid colour 1
+        chr_rom[0x10..0x18].fill(0xff);
+        let mut ppu = NesPPU::new(chr_rom, PPUMirroring::Horizontal);
+        ppu.palette_table[0] = 0x0f;
+        ppu.palette_table[1] = 0x16;
+        ppu.vram[0x400] = 1;
+        // second screen ($2800 on horizontal mirroring), scrolled by 4,2
+        ppu.write_to_control_reg(0b10);
+        ppu.scroll_register.write(4);
+        ppu.scroll_register.write(2);
+        assert_eq!(scroll_origin(&ppu), (4, 242));
+
+        let image = nametables(&ppu, false);
+        assert_eq!(image.get_pixel(1, 241), SYSTEM_PALLETE[0x16]);
+        assert_eq!(image.get_pixel(9, 243), SYSTEM_PALLETE[0x0f]);
+        assert_eq!(image.get_pixel(4, 242), VIEWPORT_COLOR);
+        // the bottom edge wraps to the top
+        assert_eq!(image.get_pixel(100, 1), VIEWPORT_COLOR);
+        assert_eq!(image.get_pixel(100, 2), SYSTEM_PALLETE[0x0f]);
+        assert_eq!(nametables(&ppu, true).get_pixel(16, 100), GRID_COLOR);
+    }
+}
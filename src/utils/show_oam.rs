@@ -0,0 +1,328 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use sdl2::{
+    event::{Event, WindowEvent},
+    keyboard::Keycode,
+    mouse::MouseButton,
+    VideoSubsystem,
+};
+
+use crate::{
+    ppu::NesPPU,
+    rendering::{image::Image, rect::Rect},
+    utils::show_tile::palette_colors,
+    utils::viewer::ViewerWindow,
+};
+
+// 64 sprites on an 8x8 grid, each in a cell big enough for 8x16 sprites
+const CELL_WIDTH: usize = 12;
+const CELL_HEIGHT: usize = 20;
+pub const OAM_WIDTH: usize = CELL_WIDTH * 8;
+pub const OAM_HEIGHT: usize = CELL_HEIGHT * 8;
+const SCALE: usize = 4;
+
+const CELL_COLOR: (u8, u8, u8) = (0x20, 0x20, 0x20);
+const DROPPED_COLOR: (u8, u8, u8) = (0x60, 0x00, 0x00);
+const SELECTED_COLOR: (u8, u8, u8) = (0xff, 0xff, 0xff);
+
+// the hardware evaluates up to 8 sprites per scanline
+const SPRITES_PER_SCANLINE: usize = 8;
+
+// an OAM entry, https://www.nesdev.org/wiki/PPU_OAM
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    pub index: usize,
+    pub x: u8,
+    pub y: u8,
+    pub tile: u8,
+    // 0-3, of the sprite palettes
+    pub palette: u8,
+    pub behind_background: bool,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+}
+
+impl Sprite {
+    pub fn decode(index: usize, entry: &[u8]) -> Sprite {
+        let attributes = entry[2];
+        Sprite {
+            index,
+            y: entry[0],
+            tile: entry[1],
+            palette: attributes & 0b11,
+            behind_background: attributes & 0b0010_0000 != 0,
+            flip_horizontal: attributes & 0b0100_0000 != 0,
+            flip_vertical: attributes & 0b1000_0000 != 0,
+            x: entry[3],
+        }
+    }
+
+    /// address of the top tile, 8x16 sprites pick their pattern table with bit 0
+    pub fn pattern_addr(&self, ppu: &NesPPU) -> u16 {
+        if ppu.control_reg.sprite_size() == 16 {
+            (self.tile as u16 & 1) * 0x1000 + (self.tile & 0xfe) as u16 * 16
+        } else {
+            ppu.control_reg.sprite_pattern_addr() + self.tile as u16 * 16
+        }
+    }
+
+    /// where the renderer draws the sprite on the screen.
+    pub fn rect(&self, ppu: &NesPPU) -> Rect {
+        let (x, y) = (self.x as usize, self.y as usize);
+        Rect::new(x, y, x + 8, y + ppu.control_reg.sprite_size() as usize)
+    }
+}
+
+// `#05 X:120 Y:64 tile:$3A palette:1 front flip:H`
+impl fmt::Display for Sprite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{:02} X:{:3} Y:{:3} tile:${:02X} palette:{} {}",
+            self.index,
+            self.x,
+            self.y,
+            self.tile,
+            self.palette,
+            if self.behind_background {
+                "back "
+            } else {
+                "front"
+            }
+        )?;
+        match (self.flip_horizontal, self.flip_vertical) {
+            (false, false) => Ok(()),
+            (true, false) => write!(f, " flip:H"),
+            (false, true) => write!(f, " flip:V"),
+            (true, true) => write!(f, " flip:HV"),
+        }
+    }
+}
+
+pub fn sprites(ppu: &NesPPU) -> Vec<Sprite> {
+    ppu.oam_data
+        .chunks(4)
+        .enumerate()
+        .map(|(index, entry)| Sprite::decode(index, entry))
+        .collect()
+}
+
+/// sprites the hardware leaves out on at least one scanline: the ones after
+/// the first 8 in OAM order on a line. the renderer still draws them all.
+pub fn dropped_sprites(ppu: &NesPPU) -> [bool; 64] {
+    let height = ppu.control_reg.sprite_size() as usize;
+    let sprites = sprites(ppu);
+    let mut dropped = [false; 64];
+    for scanline in 0..240 {
+        sprites
+            .iter()
+            .filter(|sprite| (sprite.y as usize..sprite.y as usize + height).contains(&scanline))
+            .skip(SPRITES_PER_SCANLINE)
+            .for_each(|sprite| dropped[sprite.index] = true);
+    }
+    dropped
+}
+
+fn draw_sprite(image: &mut Image, ppu: &NesPPU, sprite: &Sprite, x: usize, y: usize) {
+    let height = ppu.control_reg.sprite_size() as u16;
+    let colors = palette_colors(ppu, 4 + sprite.palette);
+    let addr = sprite.pattern_addr(ppu);
+    for row in 0..height {
+        // the second tile of 8x16 sprites follows the first one
+        let line = addr + row / 8 * 16 + row % 8;
+        let low = ppu.peek_memory(line);
+        let high = ppu.peek_memory(line + 8);
+        for column in 0..8 {
+            let shift = 7 - column;
+            let value = ((high >> shift) & 1) << 1 | ((low >> shift) & 1);
+            if value == 0 {
+                continue;
+            }
+            let dx = if sprite.flip_horizontal {
+                7 - column
+            } else {
+                column
+            };
+            let dy = if sprite.flip_vertical {
+                height - 1 - row
+            } else {
+                row
+            };
+            image.set_pixel(x + dx, y + dy as usize, colors[value as usize]);
+        }
+    }
+}
+
+/// every sprite enlarged on its own cell, dropped sprites on a red background
+/// and the selected one outlined.
+pub fn oam_sheet(ppu: &NesPPU, selected: Option<usize>) -> Image {
+    let mut image = Image::new(OAM_WIDTH, OAM_HEIGHT);
+    let dropped = dropped_sprites(ppu);
+    for sprite in sprites(ppu) {
+        let cell_x = sprite.index % 8 * CELL_WIDTH;
+        let cell_y = sprite.index / 8 * CELL_HEIGHT;
+        let background = if dropped[sprite.index] {
+            DROPPED_COLOR
+        } else {
+            CELL_COLOR
+        };
+        for y in 1..CELL_HEIGHT - 1 {
+            for x in 1..CELL_WIDTH - 1 {
+                let edge = x == 1 || y == 1 || x == CELL_WIDTH - 2 || y == CELL_HEIGHT - 2;
+                let color = if edge && selected == Some(sprite.index) {
+                    SELECTED_COLOR
+                } else {
+                    background
+                };
+                image.set_pixel(cell_x + x, cell_y + y, color);
+            }
+        }
+        draw_sprite(&mut image, ppu, &sprite, cell_x + 2, cell_y + 2);
+    }
+    image
+}
+
+// live window of the sprites, redrawn every frame. a click selects a sprite,
+// its entry goes to the title and its box to the game screen.
+// L prints all 64 entries, E saves a PNG.
+pub struct OamViewer {
+    window: ViewerWindow,
+    selected: Option<usize>,
+    list: bool,
+}
+
+impl OamViewer {
+    pub fn new(video: &VideoSubsystem, export_path: PathBuf) -> Result<Self, String> {
+        let window = ViewerWindow::new(video, "oam", OAM_WIDTH, OAM_HEIGHT, SCALE, export_path)?;
+        Ok(OamViewer {
+            window,
+            selected: None,
+            list: false,
+        })
+    }
+
+    pub fn update(&mut self, ppu: &NesPPU) -> Result<(), String> {
+        if self.list {
+            self.list = false;
+            let dropped = dropped_sprites(ppu);
+            for sprite in sprites(ppu) {
+                let note = if dropped[sprite.index] {
+                    " dropped"
+                } else {
+                    ""
+                };
+                println!("{}{}", sprite, note);
+            }
+        }
+        if let Some(index) = self.selected {
+            let sprite = Sprite::decode(index, &ppu.oam_data[index * 4..index * 4 + 4]);
+            self.window.set_title(&format!("oam - {}", sprite));
+        }
+        self.window.show(oam_sheet(ppu, self.selected))
+    }
+
+    /// screen area of the selected sprite, to outline on the game.
+    pub fn highlight(&self, ppu: &NesPPU) -> Option<Rect> {
+        let index = self.selected?;
+        Some(Sprite::decode(index, &ppu.oam_data[index * 4..index * 4 + 4]).rect(ppu))
+    }
+
+    /// true when the event was for this window.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        if !self.window.handle_common(event) {
+            return false;
+        }
+        match event {
+            Event::Window {
+                win_event: WindowEvent::Close,
+                ..
+            } => self.selected = None,
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } if *x >= 0 && *y >= 0 => {
+                let column = *x as usize / SCALE / CELL_WIDTH;
+                let row = *y as usize / SCALE / CELL_HEIGHT;
+                self.selected = (column < 8 && row < 8).then_some(row * 8 + column);
+                if self.selected.is_none() {
+                    self.window.set_title("oam");
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::L),
+                ..
+            } => self.list = true,
+            _ => {}
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ppu::{PPUMirroring, PPU};
+    use crate::rendering::SYSTEM_PALLETE;
+
+    #[test]
+    fn test_decode_sprite() {
+        let sprite = Sprite::decode(5, &[64, 0x3a, 0b0110_0001, 120]);
+        assert_eq!(
+            sprite.to_string(),
+            "#05 X:120 Y: 64 tile:$3A palette:1 back  flip:H"
+        );
+        assert!(sprite.behind_background);
+        assert!(!sprite.flip_vertical);
+
+        let mut ppu = NesPPU::new_empty_rom();
+        ppu.write_to_control_reg(0b0000_1000);
+        assert_eq!(sprite.pattern_addr(&ppu), 0x13a0);
+        // 8x16 sprites, an odd tile is in the $1000 table
+        ppu.write_to_control_reg(0b0010_0000);
+        assert_eq!(sprite.pattern_addr(&ppu), 0x03a0);
+        let sprite = Sprite::decode(0, &[0, 0x3b, 0, 0]);
+        assert_eq!(sprite.pattern_addr(&ppu), 0x13a0);
+        let rect = sprite.rect(&ppu);
+        assert_eq!((rect.x2, rect.y2), (8, 16));
+    }
+
+    #[test]
+    fn test_dropped_sprites() {
+        let mut ppu = NesPPU::new_empty_rom();
+        // 10 sprites on line 100, the last two are dropped
+        for i in 0..10 {
+            ppu.oam_data[i * 4] = 100 - i as u8 % 2;
+        }
+        for i in 10..64 {
+            ppu.oam_data[i * 4] = 0xf0;
+        }
+        let dropped = dropped_sprites(&ppu);
+        assert_eq!(dropped.iter().filter(|d| **d).count(), 2);
+        assert!(dropped[8] && dropped[9]);
+    }
+
+    #[test]
+    fn test_oam_sheet() {
+        let mut chr_rom = vec![0; 0x2000];
+        // tile 1: a single pixel of colour 3 in the top left corner
+        chr_rom[0x10] = 0x80;
+        chr_rom[0x18] = 0x80;
+        let mut ppu = NesPPU::new(chr_rom, PPUMirroring::Horizontal);
+        ppu.palette_table[0x1f] = 0x30;
+        // sprite 9 uses tile 1, palette 3, flipped horizontally
+        ppu.oam_data[36..40].copy_from_slice(&[10, 1, 0b0100_0011, 20]);
+
+        let image = oam_sheet(&ppu, Some(9));
+        let (cell_x, cell_y) = (CELL_WIDTH, CELL_HEIGHT);
+        assert_eq!(
+            image.get_pixel(cell_x + 2 + 7, cell_y + 2),
+            SYSTEM_PALLETE[0x30]
+        );
+        assert_eq!(image.get_pixel(cell_x + 2, cell_y + 2), CELL_COLOR);
+        assert_eq!(image.get_pixel(cell_x + 1, cell_y + 1), SELECTED_COLOR);
+        assert_eq!(image.get_pixel(1, 1), CELL_COLOR);
+    }
+}
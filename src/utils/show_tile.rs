@@ -1,78 +1,162 @@
-use sdl2::{
-    event::Event,
-    keyboard::Keycode,
-    render::{Canvas, Texture},
-    video::Window,
-};
+use std::path::PathBuf;
+
+use sdl2::{event::Event, keyboard::Keycode, VideoSubsystem};
 
 use crate::{
-    cartridge::rom::ROM,
-    rendering::{frame::Frame, SYSTEM_PALLETE},
+    ppu::NesPPU,
+    rendering::{image::Image, SYSTEM_PALLETE},
+    utils::viewer::ViewerWindow,
 };
 
-fn show_tile_bank(chr_rom: &Vec<u8>, bank: usize) -> Frame {
-    assert!(bank <= 1);
+// two pattern tables of 16x16 tiles, side by side
+pub const PATTERN_TABLES_WIDTH: usize = 256;
+pub const PATTERN_TABLES_HEIGHT: usize = 128;
+const SCALE: usize = 3;
 
-    let mut frame = Frame::new();
-    let mut tile_y = 0;
-    let mut tile_x = 0;
+/// the 4 colours of a palette, 0-3 for the background and 4-7 for sprites.
+/// colour 0 is the backdrop at $3F00 for every palette.
+pub fn palette_colors(ppu: &NesPPU, palette: u8) -> [(u8, u8, u8); 4] {
+    let mut colors = [(0, 0, 0); 4];
+    for (i, color) in colors.iter_mut().enumerate() {
+        let addr = if i == 0 {
+            0x3f00
+        } else {
+            0x3f00 + palette as u16 * 4 + i as u16
+        };
+        *color = SYSTEM_PALLETE[(ppu.peek_memory(addr) & 0x3f) as usize];
+    }
+    colors
+}
 
-    let bank = (bank * 0x1000) as usize;
+/// draw the tile at `addr` of the ppu address space with its top left corner at `x`, `y`.
+/// the low bit of a pixel is in the first 8 bytes, the high bit in the next 8.
+pub fn draw_tile(
+    image: &mut Image,
+    ppu: &NesPPU,
+    addr: u16,
+    x: usize,
+    y: usize,
+    colors: &[(u8, u8, u8); 4],
+) {
+    for row in 0..8 {
+        let low = ppu.peek_memory(addr + row);
+        let high = ppu.peek_memory(addr + row + 8);
+        for column in 0..8 {
+            let shift = 7 - column;
+            let value = ((high >> shift) & 1) << 1 | ((low >> shift) & 1);
+            image.set_pixel(x + column, y + row as usize, colors[value as usize]);
+        }
+    }
+}
 
-    for tile_n in 0..255 {
-        if tile_n != 0 && tile_n % 20 == 0 {
-            tile_y += 10;
-            tile_x = 0;
+/// both pattern tables as the ppu sees them now, $0000 on the left and $1000
+/// on the right. read through the ppu address space so it follows CHR banking.
+pub fn pattern_tables(ppu: &NesPPU, palette: u8) -> Image {
+    let mut image = Image::new(PATTERN_TABLES_WIDTH, PATTERN_TABLES_HEIGHT);
+    let colors = palette_colors(ppu, palette);
+    for table in 0..2 {
+        for tile in 0..256 {
+            let x = table * 128 + tile % 16 * 8;
+            let y = tile / 16 * 8;
+            let addr = (table * 0x1000 + tile * 16) as u16;
+            draw_tile(&mut image, ppu, addr, x, y, &colors);
         }
-        let tile = &chr_rom[(bank + tile_n * 16)..=(bank + tile_n * 16 + 15)];
+    }
+    image
+}
+
+// live window of the pattern tables, redrawn every frame.
+// keys 1-8 pick the palette (1-4 background, 5-8 sprites), E saves a PNG.
+pub struct PatternViewer {
+    window: ViewerWindow,
+    palette: u8,
+}
 
-        for y in 0..=7 {
-            let mut upper = tile[y];
-            let mut lower = tile[y + 8];
+impl PatternViewer {
+    pub fn new(video: &VideoSubsystem, export_path: PathBuf) -> Result<Self, String> {
+        let window = ViewerWindow::new(
+            video,
+            "pattern tables",
+            PATTERN_TABLES_WIDTH,
+            PATTERN_TABLES_HEIGHT,
+            SCALE,
+            export_path,
+        )?;
+        let mut viewer = PatternViewer { window, palette: 0 };
+        viewer.update_title();
+        Ok(viewer)
+    }
+
+    pub fn update(&mut self, ppu: &NesPPU) -> Result<(), String> {
+        self.window.show(pattern_tables(ppu, self.palette))
+    }
 
-            for x in (0..=7).rev() {
-                let value = (1 & upper) << 1 | (1 & lower);
-                upper = upper >> 1;
-                lower = lower >> 1;
-                // this random choices.
-                let rgb = match value {
-                    0 => SYSTEM_PALLETE[0x01],
-                    1 => SYSTEM_PALLETE[0x23],
-                    2 => SYSTEM_PALLETE[0x27],
-                    3 => SYSTEM_PALLETE[0x30],
-                    _ => panic!("can't be"),
-                };
-                frame.set_pixel(tile_x + x, tile_y + y, rgb);
+    /// true when the event was for this window.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        if !self.window.handle_common(event) {
+            return false;
+        }
+        if let Event::KeyDown {
+            keycode: Some(keycode),
+            ..
+        } = event
+        {
+            if let Some(palette) = palette_key(*keycode) {
+                self.palette = palette;
+                self.update_title();
             }
         }
-        tile_x += 10;
+        true
+    }
+
+    fn update_title(&mut self) {
+        let kind = if self.palette < 4 {
+            "background"
+        } else {
+            "sprite"
+        };
+        self.window.set_title(&format!(
+            "pattern tables - {} palette {}",
+            kind,
+            self.palette % 4
+        ));
     }
-    frame
 }
 
-pub fn show_tile_viewer(
-    mut canvas: Canvas<Window>,
-    mut texture: Texture<'_>,
-    mut event_pump: sdl2::EventPump,
-    nes_file_name: String,
-) {
-    let bytes: Vec<u8> = std::fs::read(nes_file_name.as_str()).unwrap();
-    let rom = ROM::new(&bytes).unwrap();
-    let tile_frame = show_tile_bank(&rom.chr_rom, 1);
-    texture.update(None, &tile_frame.data, 256 * 3).unwrap();
-    canvas.copy(&texture, None, None).unwrap();
-    canvas.present();
+// 1-8 on the number row
+fn palette_key(keycode: Keycode) -> Option<u8> {
+    let index = keycode as i32 - Keycode::Num1 as i32;
+    (0..8).contains(&index).then_some(index as u8)
+}
 
-    loop {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => std::process::exit(0),
-                _ => {}
-            }
-        }
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ppu::PPUMirroring;
+
+    #[test]
+    fn test_pattern_tables() {
+        let mut chr_rom = vec![0; 0x2000];
+        // tile 1 of the right table: a row of colours 0, 1, 2, 3
+        chr_rom[0x1010] = 0b0101_0000;
+        chr_rom[0x1018] = 0b0011_0000;
+        let mut ppu = NesPPU::new(chr_rom, PPUMirroring::Horizontal);
+        ppu.palette_table[0] = 0x0f;
+        ppu.palette_table[0x15..0x18].copy_from_slice(&[0x16, 0x27, 0x30]);
+
+        let image = pattern_tables(&ppu, 5);
+        let row = (0..4)
+            .map(|x| image.get_pixel(128 + 8 + x, 0))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            row,
+            [
+                SYSTEM_PALLETE[0x0f],
+                SYSTEM_PALLETE[0x16],
+                SYSTEM_PALLETE[0x27],
+                SYSTEM_PALLETE[0x30]
+            ]
+        );
+        assert_eq!(image.get_pixel(8, 0), SYSTEM_PALLETE[0x0f]);
     }
 }
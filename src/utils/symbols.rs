@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+// labels by cpu address, loaded from the symbol files of assemblers and emulators.
+// only NROM is supported so a PRG ROM offset always maps to the same addresses.
+#[derive(Debug, Clone, Default)]
+pub struct Symbols {
+    labels: HashMap<u16, String>,
+}
+
+impl Symbols {
+    pub fn new() -> Self {
+        Symbols::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// the first label wins, a file loaded later doesn't rename an address.
+    pub fn insert(&mut self, addr: u16, name: &str) {
+        self.labels.entry(addr).or_insert_with(|| name.to_string());
+    }
+
+    pub fn get(&self, addr: u16) -> Option<&str> {
+        self.labels.get(&addr).map(|name| name.as_str())
+    }
+
+    pub fn address_of(&self, name: &str) -> Option<u16> {
+        self.labels
+            .iter()
+            .find(|(_, label)| label.as_str() == name)
+            .map(|(addr, _)| *addr)
+    }
+
+    /// load a ca65 `.dbg`, FCEUX `.nl` or Mesen `.mlb` file, picked by extension.
+    /// `prg_rom_len` maps the PRG ROM offsets of `.mlb` files to cpu addresses.
+    pub fn load(&mut self, path: &Path, prg_rom_len: usize) -> Result<(), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("can't read symbols {}: {}", path.display(), e))?;
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "dbg" => self.parse_dbg(&text),
+            "nl" => self.parse_nl(&text),
+            "mlb" => self.parse_mlb(&text, prg_rom_len),
+            _ => Err(format!(
+                "unknown symbol file {}, expected .dbg, .nl or .mlb",
+                path.display()
+            )),
+        }
+    }
+
+    // ld65 --dbgfile, one `sym` line per symbol:
+    // sym	id=3,name="reset",addrsize=absolute,scope=0,def=5,ref=9,val=0x8000,seg=1,type=lab
+    pub fn parse_dbg(&mut self, text: &str) -> Result<(), String> {
+        for line in text.lines() {
+            let Some(fields) = line.strip_prefix("sym\t") else {
+                continue;
+            };
+            let field = |key: &str| {
+                fields
+                    .split(',')
+                    .find_map(|f| f.strip_prefix(key)?.strip_prefix('='))
+            };
+            // equates are constants, only labels are addresses
+            if field("type") != Some("lab") {
+                continue;
+            }
+            let (Some(name), Some(value)) = (field("name"), field("val")) else {
+                continue;
+            };
+            let value = value.strip_prefix("0x").unwrap_or(value);
+            let addr = u16::from_str_radix(value, 16)
+                .map_err(|e| format!("invalid symbol value in {}: {}", line, e))?;
+            self.insert(addr, name.trim_matches('"'));
+        }
+        Ok(())
+    }
+
+    // FCEUX name lists, `$C000#reset#comment`, `$0300/10#buffer#` for arrays
+    pub fn parse_nl(&mut self, text: &str) -> Result<(), String> {
+        for line in text.lines() {
+            let Some(line) = line.trim().strip_prefix('$') else {
+                continue;
+            };
+            let mut parts = line.splitn(3, '#');
+            let addr = parts.next().unwrap_or("");
+            let name = parts.next().unwrap_or("").trim();
+            let addr = addr.split('/').next().unwrap_or(addr);
+            let addr = u16::from_str_radix(addr, 16)
+                .map_err(|e| format!("invalid address in ${}: {}", line, e))?;
+            if !name.is_empty() {
+                self.insert(addr, name);
+            }
+        }
+        Ok(())
+    }
+
+    // Mesen labels, `type:address[-end]:label[:comment]`.
+    // types are memory spaces (Mesen 1 letters or Mesen 2 names):
+    // PRG ROM offsets, internal RAM, save/work RAM at $6000 and cpu registers.
+    pub fn parse_mlb(&mut self, text: &str, prg_rom_len: usize) -> Result<(), String> {
+        for line in text.lines() {
+            let mut parts = line.trim().splitn(4, ':');
+            let (Some(kind), Some(addr), Some(name)) = (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            let name = name.trim();
+            if name.is_empty() {
+                // a comment without label
+                continue;
+            }
+            let start = addr.split('-').next().unwrap_or(addr);
+            let offset = usize::from_str_radix(start, 16)
+                .map_err(|e| format!("invalid address in {}: {}", line, e))?;
+            match kind {
+                "P" | "NesPrgRom" => {
+                    for addr in prg_rom_addresses(offset, prg_rom_len) {
+                        self.insert(addr, name);
+                    }
+                }
+                "R" | "NesInternalRam" => self.insert(offset as u16 & 0x07ff, name),
+                "S" | "W" | "NesSaveRam" | "NesWorkRam" => {
+                    self.insert(0x6000 + (offset as u16 & 0x1fff), name)
+                }
+                "G" | "NesMemory" | "Register" => self.insert(offset as u16, name),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// replace the address of `operand` (the first `$` followed by hex digits)
+    /// with its label, `$2002,X` -> `PPUSTATUS,X`. immediate values are kept.
+    pub fn label_operand(&self, operand: &str) -> String {
+        let Some(start) = operand.find('$') else {
+            return operand.to_string();
+        };
+        if operand[..start].ends_with('#') {
+            return operand.to_string();
+        }
+        let digits = operand[start + 1..]
+            .chars()
+            .take_while(|c| c.is_ascii_hexdigit())
+            .count();
+        let end = start + 1 + digits;
+        let label = u16::from_str_radix(&operand[start + 1..end], 16)
+            .ok()
+            .and_then(|addr| self.get(addr));
+        match label {
+            Some(label) => format!("{}{}{}", &operand[..start], label, &operand[end..]),
+            None => operand.to_string(),
+        }
+    }
+}
+
+// cpu addresses of a PRG ROM offset on NROM, 16kib roms show up twice
+fn prg_rom_addresses(offset: usize, prg_rom_len: usize) -> Vec<u16> {
+    if offset >= prg_rom_len || offset >= 0x8000 {
+        return vec![];
+    }
+    let addr = 0x8000 + offset as u16;
+    if prg_rom_len == 0x4000 {
+        vec![addr, addr + 0x4000]
+    } else {
+        vec![addr]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_dbg() {
+        let mut symbols = Symbols::new();
+        let text = "\
+version\tmajor=2,minor=0
+sym\tid=0,name=\"reset\",addrsize=absolute,scope=0,def=5,ref=9,val=0xC000,seg=1,type=lab
+sym\tid=1,name=\"PPUCTRL\",addrsize=absolute,scope=0,def=2,val=0x2000,type=equ
+sym\tid=2,name=\"counter\",addrsize=zeropage,scope=0,def=3,val=0x10,seg=0,type=lab";
+        symbols.parse_dbg(text).unwrap();
+        assert_eq!(symbols.get(0xc000), Some("reset"));
+        assert_eq!(symbols.get(0x0010), Some("counter"));
+        assert_eq!(symbols.get(0x2000), None);
+    }
+
+    #[test]
+    fn test_parse_nl() {
+        let mut symbols = Symbols::new();
+        symbols
+            .parse_nl("$C000#reset#entry point\n$0300/10#buffer#\n$C010##only a comment\n")
+            .unwrap();
+        assert_eq!(symbols.get(0xc000), Some("reset"));
+        assert_eq!(symbols.get(0x0300), Some("buffer"));
+        assert_eq!(symbols.get(0xc010), None);
+        assert!(symbols.parse_nl("$C0X0#bad#").is_err());
+    }
+
+    #[test]
+    fn test_parse_mlb() {
+        let mut symbols = Symbols::new();
+        let text = "P:0000:reset:comment\nR:0010:counter\nS:0004-0010:message\nG:2002:PPUSTATUS\nP:0020::comment only\nNesPrgRom:0030:nmi";
+        symbols.parse_mlb(text, 0x4000).unwrap();
+        assert_eq!(symbols.get(0x8000), Some("reset"));
+        // NROM-128 is mirrored
+        assert_eq!(symbols.get(0xc000), Some("reset"));
+        assert_eq!(symbols.get(0x0010), Some("counter"));
+        assert_eq!(symbols.get(0x6004), Some("message"));
+        assert_eq!(symbols.get(0x2002), Some("PPUSTATUS"));
+        assert_eq!(symbols.get(0x8020), None);
+        assert_eq!(symbols.get(0xc030), Some("nmi"));
+        assert_eq!(symbols.address_of("counter"), Some(0x0010));
+    }
+
+    #[test]
+    fn test_label_operand() {
+        let mut symbols = Symbols::new();
+        symbols.insert(0x2002, "PPUSTATUS");
+        symbols.insert(0x0033, "ptr");
+        assert_eq!(symbols.label_operand("$2002"), "PPUSTATUS");
+        assert_eq!(symbols.label_operand("($33),Y = 0400"), "(ptr),Y = 0400");
+        assert_eq!(symbols.label_operand("$2003,X"), "$2003,X");
+        assert_eq!(symbols.label_operand("#$33"), "#$33");
+    }
+}
@@ -1,42 +1,80 @@
-use crate::cartridge::mem::Mem;
 use crate::cpu_internals::cpu::CPU;
+use crate::cpu_internals::cpu_bus::CpuBus;
 use crate::cpu_internals::opscodes::AddressingMode;
-use crate::cpu_internals::opscodes::AddressingModeConverter;
-use crate::cpu_internals::opscodes::OPCODES_MAP;
+use crate::cpu_internals::opscodes::OPCODES;
+use crate::utils::symbols::Symbols;
 
-pub fn trace(cpu: &mut CPU) -> String {
-    let code = cpu.mem_read(cpu.program_counter);
-    let ops = OPCODES_MAP.get(&code).unwrap();
+fn peek_u16<M: CpuBus>(bus: &M, addr: u16) -> u16 {
+    u16::from_le_bytes([bus.peek(addr), bus.peek(addr.wrapping_add(1))])
+}
+
+// the zero page pointer of the indirect modes wraps within page 0
+fn peek_zero_page_u16<M: CpuBus>(bus: &M, ptr: u8) -> u16 {
+    u16::from_le_bytes([bus.peek(ptr as u16), bus.peek(ptr.wrapping_add(1) as u16)])
+}
+
+// effective address of the operand at `addr`, like `get_absolute_address`
+// but only peeking at the bus.
+fn operand_address<M: CpuBus>(cpu: &CPU<M>, mode: &AddressingMode, addr: u16) -> u16 {
+    let bus = &cpu.bus;
+    match mode {
+        AddressingMode::ZeroPage => bus.peek(addr) as u16,
+        AddressingMode::ZeroPage_X => bus.peek(addr).wrapping_add(cpu.register_x) as u16,
+        AddressingMode::ZeroPage_Y => bus.peek(addr).wrapping_add(cpu.register_y) as u16,
+        AddressingMode::Absolute => peek_u16(bus, addr),
+        AddressingMode::Absolute_X => peek_u16(bus, addr).wrapping_add(cpu.register_x as u16),
+        AddressingMode::Absolute_Y => peek_u16(bus, addr).wrapping_add(cpu.register_y as u16),
+        AddressingMode::Indirect_X => {
+            peek_zero_page_u16(bus, bus.peek(addr).wrapping_add(cpu.register_x))
+        }
+        AddressingMode::Indirect_Y => {
+            peek_zero_page_u16(bus, bus.peek(addr)).wrapping_add(cpu.register_y as u16)
+        }
+        _ => 0,
+    }
+}
+
+/// one line of a Nintendulator style log (the format of nestest.log) for the
+/// instruction at PC, before it runs. memory is only peeked at so tracing
+/// doesn't change the state of the I/O registers.
+pub fn trace<M: CpuBus>(cpu: &CPU<M>) -> String {
+    format_trace(cpu, None)
+}
+
+/// `trace` with the operand address replaced by its label.
+pub fn trace_with_symbols<M: CpuBus>(cpu: &CPU<M>, symbols: &Symbols) -> String {
+    format_trace(cpu, Some(symbols))
+}
 
+fn format_trace<M: CpuBus>(cpu: &CPU<M>, symbols: Option<&Symbols>) -> String {
+    let bus = &cpu.bus;
     let begin = cpu.program_counter;
-    let mut hex_dump = vec![];
-    hex_dump.push(code);
+    let code = bus.peek(begin);
+    let ops = OPCODES[code as usize];
+
+    let hex_dump = (0..ops.bytes_len as u16)
+        .map(|i| bus.peek(begin.wrapping_add(i)))
+        .collect::<Vec<u8>>();
 
     let (mem_addr, stored_value) = match ops.mode {
         AddressingMode::Immediate | AddressingMode::NonAddressing | AddressingMode::Relative => {
             (0, 0)
         }
         _ => {
-            let (addr, _) = cpu.get_absolute_address(&ops.mode, begin + 1);
-            (addr, cpu.mem_read(addr))
+            let addr = operand_address(cpu, &ops.mode, begin.wrapping_add(1));
+            (addr, bus.peek(addr))
         }
     };
 
     let tmp = match ops.bytes_len {
         1 => match ops.code {
-            0x0a | 0x4a | 0x2a | 0x6a => format!("A "),
+            0x0a | 0x4a | 0x2a | 0x6a => "A ".to_string(),
             _ => String::from(""),
         },
         2 => {
-            let address: u8 = cpu.mem_read(begin + 1);
-            // let value = cpu.mem_read(address));
-            hex_dump.push(address);
-
+            let address = hex_dump[1];
             match ops.mode {
                 AddressingMode::Immediate => format!("#${:02x}", address),
-                AddressingMode::Relative => {
-                    format!("#${:02x}", cpu.program_counter.wrapping_add(address as u16))
-                }
                 AddressingMode::ZeroPage => format!("${:02x} = {:02x}", mem_addr, stored_value),
                 AddressingMode::ZeroPage_X => format!(
                     "${:02x},X @ {:02x} = {:02x}",
@@ -60,13 +98,11 @@ pub fn trace(cpu: &mut CPU) -> String {
                     mem_addr,
                     stored_value
                 ),
-                AddressingMode::NonAddressing => {
-                    // assuming local jumps: BNE, BVS, etc....
-                    let address: usize =
-                        (begin as usize + 2).wrapping_add((address as i8) as usize);
-                    format!("${:04x}", address)
+                AddressingMode::Relative | AddressingMode::NonAddressing => {
+                    // branch target, relative to the next instruction
+                    let target = begin.wrapping_add(2).wrapping_add(address as i8 as u16);
+                    format!("${:04x}", target)
                 }
-
                 _ => panic!(
                     "unexpected addressing mode {:?} has ops-len 2. code {:02x}",
                     ops.mode, ops.code
@@ -74,26 +110,18 @@ pub fn trace(cpu: &mut CPU) -> String {
             }
         }
         3 => {
-            let address_lo = cpu.mem_read(begin + 1);
-            let address_hi = cpu.mem_read(begin + 2);
-            hex_dump.push(address_lo);
-            hex_dump.push(address_hi);
-
-            let address = cpu.mem_read_u16(begin + 1);
-
+            let address = u16::from_le_bytes([hex_dump[1], hex_dump[2]]);
             match ops.mode {
                 AddressingMode::NonAddressing => {
                     if ops.code == 0x6c {
-                        //jmp indirect
+                        //jmp indirect, the pointer doesn't cross pages
                         let jmp_addr = if address & 0x00FF == 0x00FF {
-                            let lo = cpu.mem_read(address);
-                            let hi = cpu.mem_read(address & 0xFF00);
+                            let lo = bus.peek(address);
+                            let hi = bus.peek(address & 0xFF00);
                             (hi as u16) << 8 | (lo as u16)
                         } else {
-                            cpu.mem_read_u16(address)
+                            peek_u16(bus, address)
                         };
-
-                        // let jmp_addr = cpu.mem_read_u16(address);
                         format!("(${:04x}) = {:04x}", address, jmp_addr)
                     } else {
                         format!("${:04x}", address)
@@ -117,33 +145,45 @@ pub fn trace(cpu: &mut CPU) -> String {
         }
         _ => String::from(""),
     };
+    // upper case hex first, labels keep their case
+    let tmp = tmp.to_ascii_uppercase();
+    let tmp = match symbols {
+        Some(symbols) => symbols.label_operand(&tmp),
+        None => tmp,
+    };
 
     let hex_str = hex_dump
         .iter()
-        .map(|z| format!("{:02x}", z))
+        .map(|z| format!("{:02X}", z))
         .collect::<Vec<String>>()
         .join(" ");
-    let asm_str = format!("{:04x}  {:8} {: >4} {}", begin, hex_str, ops.name, tmp)
+    // illegal opcodes are named with a leading `*` which takes the place of the space
+    let asm_str = format!("{:04X}  {:8} {: >4} {}", begin, hex_str, ops.name, tmp)
         .trim()
         .to_string();
 
-    format!(
-        "{:47} A:{:02x} X:{:02x} Y:{:02x} P:{:02x} SP:{:02x}",
+    let mut line = format!(
+        "{:47} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
         asm_str, cpu.register_a, cpu.register_x, cpu.register_y, cpu.status, cpu.stack_pointer,
-    )
-    .to_ascii_uppercase()
+    );
+    if let Some((scanline, dot)) = bus.ppu_position() {
+        line += &format!(" PPU:{:>3},{:>3}", scanline, dot);
+    }
+    line += &format!(" CYC:{}", bus.cycles());
+    line
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::bus::Bus;
+    use crate::cartridge::mem::Mem;
     use crate::cartridge::rom::test::test_rom;
-    use crate::ppu::NesPPU;
+    use crate::cpu_internals::cpu_bus::FlatBus;
 
     #[test]
     fn test_format_trace() {
-        let mut bus = Bus::new(test_rom(), |ppu: &NesPPU| {});
+        let mut bus = FlatBus::new();
         bus.mem_write(100, 0xa2);
         bus.mem_write(101, 0x01);
         bus.mem_write(102, 0xca);
@@ -152,30 +192,33 @@ mod test {
 
         let mut cpu = CPU::new(bus);
         cpu.program_counter = 0x64;
+        cpu.stack_pointer = 0xfd;
         cpu.register_a = 1;
         cpu.register_x = 2;
         cpu.register_y = 3;
         let mut result: Vec<String> = vec![];
         cpu.run_with_callback(|cpu| {
             result.push(trace(cpu));
-        });
+            cpu.halted = result.len() == 3;
+        })
+        .unwrap();
         assert_eq!(
-            "0064  A2 01     LDX #$01                        A:01 X:02 Y:03 P:24 SP:FD",
+            "0064  A2 01     LDX #$01                        A:01 X:02 Y:03 P:24 SP:FD CYC:0",
             result[0]
         );
         assert_eq!(
-            "0066  CA        DEX                             A:01 X:01 Y:03 P:24 SP:FD",
+            "0066  CA        DEX                             A:01 X:01 Y:03 P:24 SP:FD CYC:2",
             result[1]
         );
         assert_eq!(
-            "0067  88        DEY                             A:01 X:00 Y:03 P:26 SP:FD",
+            "0067  88        DEY                             A:01 X:00 Y:03 P:26 SP:FD CYC:4",
             result[2]
         );
     }
 
     #[test]
     fn test_format_mem_access() {
-        let mut bus = Bus::new(test_rom(), |ppu: &NesPPU| {});
+        let mut bus = FlatBus::new();
         // ORA ($33), Y
         bus.mem_write(100, 0x11);
         bus.mem_write(101, 0x33);
@@ -189,14 +232,87 @@ mod test {
 
         let mut cpu = CPU::new(bus);
         cpu.program_counter = 0x64;
+        cpu.stack_pointer = 0xfd;
         cpu.register_y = 0;
         let mut result: Vec<String> = vec![];
         cpu.run_with_callback(|cpu| {
             result.push(trace(cpu));
-        });
+            cpu.halted = true;
+        })
+        .unwrap();
         assert_eq!(
-            "0064  11 33     ORA ($33),Y = 0400 @ 0400 = AA  A:00 X:00 Y:00 P:24 SP:FD",
+            "0064  11 33     ORA ($33),Y = 0400 @ 0400 = AA  A:00 X:00 Y:00 P:24 SP:FD CYC:0",
             result[0]
         );
     }
+
+    #[test]
+    fn test_format_illegal_opcode_and_branch() {
+        let mut bus = FlatBus::new();
+        // *NOP $A9, BNE -4
+        bus.mem_write(0x64, 0x04);
+        bus.mem_write(0x65, 0xa9);
+        bus.mem_write(0x66, 0xd0);
+        bus.mem_write(0x67, 0xfc);
+
+        let mut cpu = CPU::new(bus);
+        cpu.program_counter = 0x64;
+        cpu.stack_pointer = 0xfd;
+        assert_eq!(
+            "0064  04 A9    *NOP $A9 = 00                    A:00 X:00 Y:00 P:24 SP:FD CYC:0",
+            trace(&cpu)
+        );
+        cpu.program_counter = 0x66;
+        assert_eq!(
+            "0066  D0 FC     BNE $0064                       A:00 X:00 Y:00 P:24 SP:FD CYC:0",
+            trace(&cpu)
+        );
+    }
+
+    #[test]
+    fn test_trace_has_no_side_effects() {
+        let mut bus = Bus::new(test_rom(), |_, _, _| {});
+        // LDA $2002
+        bus.mem_write(0x10, 0xad);
+        bus.mem_write(0x11, 0x02);
+        bus.mem_write(0x12, 0x20);
+        bus.ppu.status_reg.update_vertical_blank_started(true);
+        bus.ppu.scanlines = 241;
+        bus.ppu.cycles = 5;
+
+        let mut cpu = CPU::new(bus);
+        cpu.program_counter = 0x10;
+        cpu.stack_pointer = 0xfd;
+        let line = trace(&cpu);
+        assert!(line.starts_with("0010  AD 02 20  LDA $2002 = "), "{}", line);
+        assert!(line.ends_with("SP:FD PPU:241,  5 CYC:0"), "{}", line);
+        assert!(cpu.bus.ppu.status_reg.is_in_vblank());
+    }
+
+    #[test]
+    fn test_trace_with_symbols() {
+        let mut bus = FlatBus::new();
+        // STA $0200,X / LDA #$02
+        bus.mem_write(0x64, 0x9d);
+        bus.mem_write(0x65, 0x00);
+        bus.mem_write(0x66, 0x02);
+        bus.mem_write(0x67, 0xa9);
+        bus.mem_write(0x68, 0x02);
+
+        let mut cpu = CPU::new(bus);
+        cpu.program_counter = 0x64;
+        cpu.stack_pointer = 0xfd;
+        let mut symbols = Symbols::new();
+        symbols.insert(0x0200, "oam_buffer");
+        symbols.insert(0x0002, "temp");
+        assert_eq!(
+            "0064  9D 00 02  STA oam_buffer,X @ 0200 = 00    A:00 X:00 Y:00 P:24 SP:FD CYC:0",
+            trace_with_symbols(&cpu, &symbols)
+        );
+        cpu.program_counter = 0x67;
+        assert_eq!(
+            "0067  A9 02     LDA #$02                        A:00 X:00 Y:00 P:24 SP:FD CYC:0",
+            trace_with_symbols(&cpu, &symbols)
+        );
+    }
 }
@@ -0,0 +1,107 @@
+use std::path::{Path, PathBuf};
+
+use sdl2::{
+    event::{Event, WindowEvent},
+    keyboard::Keycode,
+    pixels::PixelFormatEnum,
+    render::Canvas,
+    video::Window,
+    VideoSubsystem,
+};
+
+use crate::rendering::image::Image;
+
+// a debug window next to the game showing an `Image` scaled up,
+// the last image shown is kept to export it.
+// closing it only hides it, `E` saves the image to `export_path`.
+pub struct ViewerWindow {
+    canvas: Canvas<Window>,
+    image: Image,
+    export_path: PathBuf,
+}
+
+impl ViewerWindow {
+    pub fn new(
+        video: &VideoSubsystem,
+        title: &str,
+        width: usize,
+        height: usize,
+        scale: usize,
+        export_path: PathBuf,
+    ) -> Result<Self, String> {
+        let window = video
+            .window(title, (width * scale) as u32, (height * scale) as u32)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        Ok(ViewerWindow {
+            canvas,
+            image: Image::new(width, height),
+            export_path,
+        })
+    }
+
+    /// true when the event was for this window, then a close or an `E` was
+    /// handled here and the viewer can look at the event for its own keys.
+    pub fn handle_common(&mut self, event: &Event) -> bool {
+        if event.get_window_id() != Some(self.id()) {
+            return false;
+        }
+        match event {
+            Event::Window {
+                win_event: WindowEvent::Close,
+                ..
+            } => self.hide(),
+            Event::KeyDown {
+                keycode: Some(Keycode::E),
+                ..
+            } => self.export(&self.export_path),
+            _ => {}
+        }
+        true
+    }
+
+    /// save the image shown as a png at `path`.
+    pub fn export(&self, path: &Path) {
+        match self.image.save_png(path) {
+            Ok(()) => println!("saved {}", path.display()),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    /// id of the window, to route the sdl events
+    pub fn id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        // a title with a nul byte is the only error, keep the old one then
+        let _ = self.canvas.window_mut().set_title(title);
+    }
+
+    pub fn hide(&mut self) {
+        self.canvas.window_mut().hide();
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    pub fn show(&mut self, image: Image) -> Result<(), String> {
+        let creator = self.canvas.texture_creator();
+        let mut texture = creator
+            .create_texture_streaming(
+                PixelFormatEnum::RGB24,
+                image.width as u32,
+                image.height as u32,
+            )
+            .map_err(|e| e.to_string())?;
+        texture
+            .update(None, &image.data, image.pitch())
+            .map_err(|e| e.to_string())?;
+        self.canvas.copy(&texture, None, None)?;
+        self.canvas.present();
+        self.image = image;
+        Ok(())
+    }
+}
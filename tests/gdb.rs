@@ -0,0 +1,103 @@
+// drives the gdb stub over a local socket, like a remote debugging client would.
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::thread;
+
+use rust_nes::cartridge::mem::Mem;
+use rust_nes::cpu_internals::cpu::CPU;
+use rust_nes::cpu_internals::cpu_bus::FlatBus;
+use rust_nes::debugger::gdb::GdbStub;
+use rust_nes::debugger::watch_bus::WatchBus;
+
+// $8000: LDX #$01 / INX / STX $0200 / JMP $8002
+const PROGRAM: [u8; 9] = [0xa2, 0x01, 0xe8, 0x8e, 0x00, 0x02, 0x4c, 0x02, 0x80];
+
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    fn send(&mut self, data: &str) {
+        let checksum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        write!(self.stream, "${}#{:02x}", data, checksum).unwrap();
+    }
+
+    fn read_byte(&mut self) -> u8 {
+        let mut byte = [0u8];
+        self.stream.read_exact(&mut byte).unwrap();
+        byte[0]
+    }
+
+    // the next packet, acks are skipped
+    fn receive(&mut self) -> String {
+        while self.read_byte() != b'$' {}
+        let mut data = vec![];
+        loop {
+            match self.read_byte() {
+                b'#' => break,
+                byte => data.push(byte),
+            }
+        }
+        self.read_byte();
+        self.read_byte();
+        self.stream.write_all(b"+").unwrap();
+        String::from_utf8(data).unwrap()
+    }
+
+    fn request(&mut self, data: &str) -> String {
+        self.send(data);
+        self.receive()
+    }
+}
+
+#[test]
+fn test_gdb_session() {
+    let (addr_sender, addr_receiver) = mpsc::channel();
+    let emulator = thread::spawn(move || {
+        let mut bus = FlatBus::new();
+        for (i, byte) in PROGRAM.iter().enumerate() {
+            bus.mem_write(0x8000 + i as u16, *byte);
+        }
+        let mut cpu = CPU::new(WatchBus::new(bus));
+        cpu.program_counter = 0x8000;
+        cpu.stack_pointer = 0xfd;
+
+        let mut stub = GdbStub::listen("127.0.0.1:0").unwrap();
+        addr_sender.send(stub.local_addr().unwrap()).unwrap();
+        cpu.run_with_callback(|cpu| stub.on_instruction(cpu))
+            .unwrap();
+    });
+
+    let stream = TcpStream::connect(addr_receiver.recv().unwrap()).unwrap();
+    stream.set_nodelay(true).unwrap();
+    let mut client = Client { stream };
+    assert!(client.request("qSupported:swbreak+").contains("PacketSize"));
+    assert_eq!(client.request("?"), "S05");
+    // A X Y P SP PC
+    assert_eq!(client.request("g"), "00000024fd0080");
+    assert_eq!(client.request("m8000,3"), "a201e8");
+
+    assert_eq!(client.request("Z0,8003,1"), "OK");
+    assert_eq!(client.request("c"), "T05swbreak:;");
+    assert_eq!(client.request("p5"), "0380");
+    assert_eq!(client.request("p1"), "02");
+    assert_eq!(client.request("z0,8003,1"), "OK");
+
+    assert_eq!(client.request("s"), "S05");
+    assert_eq!(client.request("p5"), "0680");
+
+    assert_eq!(client.request("Z2,200,1"), "OK");
+    assert_eq!(client.request("c"), "T05watch:0200;");
+    assert_eq!(client.request("m200,1"), "03");
+
+    assert_eq!(client.request("M0300,2:abcd"), "OK");
+    assert_eq!(client.request("m0300,2"), "abcd");
+    assert_eq!(client.request("P0=42"), "OK");
+    assert_eq!(client.request("p0"), "42");
+    assert_eq!(client.request("m8000"), "E01");
+    assert_eq!(client.request("vMustReplyEmpty"), "");
+
+    client.send("k");
+    emulator.join().unwrap();
+}
@@ -0,0 +1,151 @@
+// records a movie of a small rom that paints the backdrop with the joypad
+// state, then plays it back from power-on and expects the very same frame.
+use std::cell::Cell;
+
+use rust_nes::bus::Bus;
+use rust_nes::cartridge::rom::ROM;
+use rust_nes::cpu_internals::cpu::CPU;
+use rust_nes::input;
+use rust_nes::joypad::JoypadButton;
+use rust_nes::movie::{Movie, MovieSession};
+use rust_nes::region::Region;
+
+const FRAMES: usize = 60;
+
+// reset: wait two vblanks, enable NMI and the background, loop.
+// nmi: read the 8 buttons into $00, write them to the backdrop colour at $3F00.
+const PROGRAM: [u8; 0x51] = [
+    0x78, // C000 SEI
+    0xa9, 0x00, 0x8d, 0x01, 0x20, // C001 LDA #$00 / STA $2001
+    0x2c, 0x02, 0x20, 0x10, 0xfb, // C006 BIT $2002 / BPL $C006
+    0x2c, 0x02, 0x20, 0x10, 0xfb, // C00B BIT $2002 / BPL $C00B
+    0xa9, 0x80, 0x8d, 0x00, 0x20, // C010 LDA #$80 / STA $2000
+    0xa9, 0x0a, 0x8d, 0x01, 0x20, // C015 LDA #$0A / STA $2001
+    0x4c, 0x1a, 0xc0, // C01A JMP $C01A
+    0xa9, 0x01, 0x8d, 0x16, 0x40, // C01D LDA #$01 / STA $4016
+    0xa9, 0x00, 0x8d, 0x16, 0x40, // C022 LDA #$00 / STA $4016
+    0xa2, 0x08, // C027 LDX #$08
+    0xad, 0x16, 0x40, 0x4a, 0x26, 0x00, // C029 LDA $4016 / LSR A / ROL $00
+    0xca, 0xd0, 0xf7, // C02F DEX / BNE $C029
+    0xa9, 0x3f, 0x8d, 0x06, 0x20, // C032 LDA #$3F / STA $2006
+    0xa9, 0x00, 0x8d, 0x06, 0x20, // C037 LDA #$00 / STA $2006
+    0xa5, 0x00, 0x29, 0x3f, 0x8d, 0x07, 0x20, // C03C LDA $00 / AND #$3F / STA $2007
+    0xa9, 0x00, 0x8d, 0x05, 0x20, 0x8d, 0x05, 0x20, // C043 LDA #$00 / STA $2005 / STA $2005
+    0xa9, 0x80, 0x8d, 0x00, 0x20, // C04B LDA #$80 / STA $2000
+    0x40, // C050 RTI
+];
+
+// reset: wait WARM_UP_FRAMES vblanks with NMI off, then enable it and loop.
+// nmi: return.
+const WARM_UP_FRAMES: usize = 10;
+const WARM_UP_PROGRAM: [u8; 20] = [
+    0xa2,
+    WARM_UP_FRAMES as u8, // C000 LDX #WARM_UP_FRAMES
+    0x2c,
+    0x02,
+    0x20,
+    0x10,
+    0xfb, // C002 BIT $2002 / BPL $C002
+    0xca,
+    0xd0,
+    0xf8, // C007 DEX / BNE $C002
+    0xa9,
+    0x80,
+    0x8d,
+    0x00,
+    0x20, // C00A LDA #$80 / STA $2000
+    0x4c,
+    0x0f,
+    0xc0, // C00F JMP $C00F
+    0xea,
+    0x40, // C012 NOP / RTI
+];
+
+fn test_rom() -> ROM {
+    nrom(&PROGRAM, 0xc01d)
+}
+
+// NROM, one 16kib PRG bank at $C000 holding `program`, 8kib of blank CHR
+fn nrom(program: &[u8], nmi: u16) -> ROM {
+    let mut raw = vec![
+        0x4e, 0x45, 0x53, 0x1a, 0x01, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let mut prg = vec![0xea; 0x4000];
+    prg[..program.len()].copy_from_slice(program);
+    // RESET and IRQ $C000
+    let [nmi_lo, nmi_hi] = nmi.to_le_bytes();
+    prg[0x3ffa..].copy_from_slice(&[nmi_lo, nmi_hi, 0x00, 0xc0, 0x00, 0xc0]);
+    raw.extend(prg);
+    raw.extend(vec![0; 0x2000]);
+    ROM::new(&raw).unwrap()
+}
+
+// FNV-1a of the frame buffer
+fn hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// run FRAMES frames from power-on, `input` presses buttons like a player would,
+// returns the hash of the last frame and the movie.
+fn run(movie: Option<MovieSession>, input: impl Fn(usize) -> u8) -> (u64, Option<MovieSession>) {
+    let frames = Cell::new(0);
+    let last_hash = Cell::new(0);
+    let mut bus = Bus::new(test_rom(), |_, ports, frame| {
+        let joypad = input::player_joypad(ports, 0).unwrap();
+        joypad.button_status = JoypadButton::from_bits_truncate(input(frames.get()));
+        last_hash.set(hash(&frame.data));
+        frames.set(frames.get() + 1);
+    });
+    bus.movie = movie;
+    let mut cpu = CPU::new(bus);
+    cpu.reset();
+    cpu.run_with_callback(|cpu| {
+        if frames.get() >= FRAMES {
+            cpu.halted = true;
+        }
+    })
+    .unwrap();
+    (last_hash.get(), cpu.bus.movie.take())
+}
+
+#[test]
+fn test_movie_playback_is_deterministic() {
+    let rom = test_rom();
+    let recording = MovieSession::record(Movie::new(&rom, "movie", Region::NTSC));
+    let (recorded_hash, recording) = run(Some(recording), |frame| (frame * 37 % 251) as u8 | 1);
+    let text = recording.unwrap().movie.to_fm2();
+    // and the two vblanks the rom waits for before turning NMI on
+    assert_eq!(
+        text.lines().filter(|line| line.starts_with('|')).count(),
+        FRAMES + 2
+    );
+
+    // the keyboard does nothing while a movie plays
+    let movie = Movie::parse(&text).unwrap();
+    let (played_hash, playback) = run(Some(MovieSession::play(movie.clone())), |_| 0xff);
+    assert_eq!(played_hash, recorded_hash);
+    assert!(playback.unwrap().finished());
+
+    let (again, _) = run(Some(MovieSession::play(movie)), |_| 0);
+    assert_eq!(again, recorded_hash);
+    let (without_input, _) = run(None, |_| 0);
+    assert_ne!(without_input, recorded_hash);
+}
+
+#[test]
+fn test_movie_counts_frames_without_nmi() {
+    let nmis = Cell::new(0);
+    let mut bus = Bus::new(nrom(&WARM_UP_PROGRAM, 0xc013), |_, _, _| {
+        nmis.set(nmis.get() + 1);
+    });
+    let movie = Movie::new(&bus.rom, "movie", Region::NTSC);
+    bus.movie = Some(MovieSession::record(movie));
+    let mut cpu = CPU::new(bus);
+    cpu.reset();
+    cpu.run_with_callback(|cpu| cpu.halted = nmis.get() == FRAMES)
+        .unwrap();
+    let movie = cpu.bus.movie.take().unwrap().movie;
+    assert_eq!(movie.frames.len(), WARM_UP_FRAMES + FRAMES);
+}
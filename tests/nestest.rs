@@ -0,0 +1,156 @@
+// runs nestest.nes in automation mode ($C000) and compares the trace with nestest.log.
+// the rom is not part of the repo: put it at ./nestest.nes or point NESTEST_ROM at it,
+// the test is skipped otherwise.
+use std::path::PathBuf;
+
+use rust_nes::bus::Bus;
+use rust_nes::cartridge::rom::ROM;
+use rust_nes::cpu_internals::cpu::CPU;
+use rust_nes::utils::trace::trace;
+
+// lines shown before the first divergence
+const CONTEXT_LINES: usize = 5;
+// the cpu state columns of a trace line, PPU and CYC only when both logs have them
+const STATE_FIELDS: [&str; 7] = ["A", "X", "Y", "P", "SP", "PPU", "CYC"];
+
+fn manifest_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(name)
+}
+
+// split a trace line into (name, value) pairs, the instruction column first.
+fn fields(line: &str) -> Vec<(&str, String)> {
+    let split = line.find(" A:").unwrap_or(line.len());
+    let (instruction, state) = line.split_at(split);
+    let mut result = vec![("instruction", instruction.trim().to_string())];
+
+    let mut starts = STATE_FIELDS
+        .iter()
+        .filter_map(|name| {
+            state
+                .find(&format!(" {}:", name))
+                .map(|start| (start, *name))
+        })
+        .collect::<Vec<(usize, &str)>>();
+    starts.sort();
+    for (i, (start, name)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(state.len(), |(end, _)| *end);
+        let value = &state[start + name.len() + 2..end];
+        result.push((name, value.trim().to_string()));
+    }
+    result
+}
+
+// fields that differ, ignoring the ones missing on either side.
+fn differences(expected: &str, actual: &str) -> Vec<String> {
+    let actual_fields = fields(actual);
+    fields(expected)
+        .into_iter()
+        .filter_map(|(name, expected_value)| {
+            let (_, actual_value) = actual_fields.iter().find(|(n, _)| *n == name)?;
+            if *actual_value == expected_value {
+                None
+            } else {
+                Some(format!(
+                    "{}: expected {:?} got {:?}",
+                    name, expected_value, actual_value
+                ))
+            }
+        })
+        .collect()
+}
+
+fn first_divergence(expected: &[&str], actual: &[String]) -> Option<String> {
+    for (i, expected_line) in expected.iter().enumerate() {
+        let actual_line = match actual.get(i) {
+            Some(line) => line,
+            None => {
+                return Some(format!(
+                    "trace stopped after {} lines, expected {}",
+                    actual.len(),
+                    expected.len()
+                ))
+            }
+        };
+        let diffs = differences(expected_line, actual_line);
+        if diffs.is_empty() {
+            continue;
+        }
+        let mut report = format!("first divergence at line {}\n", i + 1);
+        for line in &expected[i.saturating_sub(CONTEXT_LINES)..i] {
+            report += &format!("           {}\n", line);
+        }
+        report += &format!("expected > {}\n", expected_line);
+        report += &format!("actual   > {}\n", actual_line);
+        for diff in diffs {
+            report += &format!("  {}\n", diff);
+        }
+        return Some(report);
+    }
+    None
+}
+
+#[test]
+fn test_nestest() {
+    let rom_path = std::env::var("NESTEST_ROM")
+        .map(PathBuf::from)
+        .unwrap_or(manifest_path("nestest.nes"));
+    let raw = match std::fs::read(&rom_path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            eprintln!("skipping nestest, can't read {}: {}", rom_path.display(), e);
+            return;
+        }
+    };
+    let log = std::fs::read_to_string(manifest_path("nestest.log")).unwrap();
+    let expected = log
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<&str>>();
+
+    let rom = ROM::new(&raw).unwrap();
+    let bus = Bus::new(rom, |_, _, _| {});
+    let mut cpu = CPU::new(bus);
+    cpu.reset();
+    cpu.program_counter = 0xc000;
+
+    let mut actual: Vec<String> = vec![];
+    cpu.run_with_callback(|cpu| {
+        actual.push(trace(cpu));
+        cpu.halted = actual.len() == expected.len();
+    })
+    .unwrap();
+
+    if let Some(report) = first_divergence(&expected, &actual) {
+        panic!("{}", report);
+    }
+}
+
+#[test]
+fn test_first_divergence() {
+    let expected = [
+        "C72E  38        SEC                             A:00 X:00 Y:00 P:26 SP:FB PPU:  0, 36 CYC:12",
+        "C72F  B0 04     BCS $C735                       A:00 X:00 Y:00 P:27 SP:FB PPU:  0, 42 CYC:14",
+        "C735  EA        NOP                             A:00 X:00 Y:00 P:27 SP:FB PPU:  0, 51 CYC:17",
+    ];
+    // no PPU and CYC columns: only the cpu state is compared
+    let actual = [
+        "C72E  38        SEC                             A:00 X:00 Y:00 P:26 SP:FB",
+        "C72F  B0 04     BCS $C735                       A:00 X:00 Y:00 P:27 SP:FB",
+        "C735  EA        NOP                             A:01 X:00 Y:00 P:27 SP:FB",
+    ]
+    .map(String::from);
+    assert_eq!(first_divergence(&expected[..2], &actual), None);
+
+    let report = first_divergence(&expected, &actual).unwrap();
+    assert!(report.starts_with("first divergence at line 3"));
+    assert!(report.contains("A: expected \"00\" got \"01\""));
+
+    let mut late = actual.clone();
+    late[1] = late[1].replace("SP:FB", "SP:FB PPU:  0, 45 CYC:15");
+    let report = first_divergence(&expected[..2], &late).unwrap();
+    assert!(report.contains("PPU: expected \"0, 42\" got \"0, 45\""));
+    assert!(report.contains("CYC: expected \"14\" got \"15\""));
+
+    let report = first_divergence(&expected, &actual[..1]).unwrap();
+    assert_eq!(report, "trace stopped after 1 lines, expected 3");
+}
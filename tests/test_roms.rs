@@ -0,0 +1,68 @@
+// runs every .nes file under ./test_roms (or TEST_ROMS_DIR) through the blargg
+// $6000 protocol and fails with the list of roms that did not pass.
+// the roms are not part of the repo, the test is skipped without the directory.
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rust_nes::cartridge::rom::ROM;
+use rust_nes::test_rom::run_test_rom;
+
+// emulated time a single rom gets before it counts as hung
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+fn collect_roms(dir: &Path, roms: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_roms(&path, roms)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("nes"))
+        {
+            roms.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_roms() {
+    let dir = std::env::var("TEST_ROMS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_roms"));
+    let mut roms = vec![];
+    if let Err(e) = collect_roms(&dir, &mut roms) {
+        eprintln!("skipping test roms, can't read {}: {}", dir.display(), e);
+        return;
+    }
+    roms.sort();
+
+    let mut failures = vec![];
+    for path in &roms {
+        let raw = std::fs::read(path).unwrap();
+        let result = ROM::new(&raw).and_then(|rom| run_test_rom(rom, TIMEOUT));
+        let failure = match result {
+            Ok(result) => {
+                println!(
+                    "{}: {:?}\n{}",
+                    path.display(),
+                    result.status,
+                    result.message
+                );
+                if result.passed() {
+                    continue;
+                }
+                format!("{:?} {}", result.status, result.message)
+            }
+            Err(e) => e,
+        };
+        failures.push(format!("{}: {}", path.display(), failure));
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} test roms failed\n{}",
+        failures.len(),
+        roms.len(),
+        failures.join("\n")
+    );
+}